@@ -0,0 +1,153 @@
+use crate::{Client, RedisError};
+
+///Options of new connection applied by `connect` in order `AUTH`, `HELLO`, `SELECT`, `PING`
+///
+///Authentication with RESP3 is sent as one `HELLO 3 AUTH` command
+///```no_run
+///use redis_client::Client;
+///
+///let client = Client::builder("127.0.0.1:6379")
+///    .auth_user("app", "secret")
+///    .db(2)
+///    .verify_with_ping(true)
+///    .connect()
+///    .unwrap();
+///```
+#[derive(Clone)]
+pub struct ClientBuilder {
+    addr: String,
+    username: Option<String>,
+    password: Option<String>,
+    db: Option<i64>,
+    resp3: bool,
+    verify_with_ping: bool,
+}
+
+impl std::fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("addr", &self.addr)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("db", &self.db)
+            .field("resp3", &self.resp3)
+            .field("verify_with_ping", &self.verify_with_ping)
+            .finish()
+    }
+}
+
+impl ClientBuilder {
+    pub fn new<A: ToString>(addr: A) -> Self {
+        ClientBuilder {
+            addr: addr.to_string(),
+            username: None,
+            password: None,
+            db: None,
+            resp3: false,
+            verify_with_ping: false,
+        }
+    }
+
+    ///Authenticate by password
+    pub fn auth(mut self, password: &str) -> Self {
+        self.username = None;
+        self.password = Some(password.to_string());
+        self
+    }
+
+    ///Authenticate as ACL user
+    pub fn auth_user(mut self, username: &str, password: &str) -> Self {
+        self.username = Some(username.to_string());
+        self.password = Some(password.to_string());
+        self
+    }
+
+    ///Select database after connect
+    pub fn db(mut self, db: i64) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    ///Switch connection to RESP3 by `HELLO 3`
+    pub fn resp3(mut self, resp3: bool) -> Self {
+        self.resp3 = resp3;
+        self
+    }
+
+    ///Send `PING` after connection is set up and fail unless server answers `+PONG`, off by default
+    ///
+    ///Catches half-open connections before first command
+    pub fn verify_with_ping(mut self, verify: bool) -> Self {
+        self.verify_with_ping = verify;
+        self
+    }
+
+    ///Connect and set up connection, fails on first rejected step
+    pub fn connect(self) -> Result<Client, RedisError> {
+        let mut client = Client::connect_host(&self.addr)?;
+
+        match (&self.username, &self.password, self.resp3) {
+            //`HELLO` requires username, password alone authenticates `default` user
+            (username, Some(password), true) => {
+                client.hello_auth(3, username.as_deref().unwrap_or("default"), password)?;
+            },
+            (Some(username), Some(password), false) => client.auth_user(username, password)?,
+            (None, Some(password), false) => client.auth(password)?,
+            (_, None, true) => {
+                client.hello(3)?;
+            },
+            (_, None, false) => {},
+        }
+        if let Some(db) = self.db {
+            client.select(db)?;
+        }
+        if self.verify_with_ping {
+            client.ping()?;
+        }
+
+        Ok(client)
+    }
+}
+
+impl Client {
+    ///Start builder of client connected to `addr`
+    pub fn builder<A: ToString>(addr: A) -> ClientBuilder {
+        ClientBuilder::new(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{Client, RedisError};
+
+    #[test]
+    fn connect_runs_setup_in_order() {
+        let server = MockServer::new(&["+OK\r\n", "+OK\r\n", "+PONG\r\n"]);
+
+        Client::builder(server.addr()).auth("secret").db(2).verify_with_ping(true).connect().unwrap();
+        assert_eq!(vec![vec!["AUTH", "secret"], vec!["SELECT", "2"], vec!["PING"]], server.commands());
+    }
+
+    #[test]
+    fn resp3_auth_in_one_hello() {
+        let server = MockServer::new(&["%1\r\n$5\r\nproto\r\n:3\r\n", "+OK\r\n"]);
+
+        Client::builder(server.addr()).auth_user("app", "secret").resp3(true).db(1).connect().unwrap();
+        assert_eq!(vec![vec!["HELLO", "3", "AUTH", "app", "secret"], vec!["SELECT", "1"]], server.commands());
+    }
+
+    #[test]
+    fn connect_fails_on_rejected_ping() {
+        let server = MockServer::new(&["-ERR server is shutting down\r\n"]);
+
+        match Client::builder(server.addr()).verify_with_ping(true).connect() {
+            Err(RedisError::Server { code, message }) => {
+                assert_eq!("ERR", code);
+                assert_eq!("server is shutting down", message);
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert_eq!(vec![vec!["PING"]], server.commands());
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    fmt,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+///Source of time for timeouts, deadlines and retry delays, replaced by `MockClock` in tests
+pub(crate) trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    fn sleep(&self, duration: Duration);
+}
+
+///Wall clock
+#[derive(Debug)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub(crate) fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+///Clock advanced only by `sleep` and `advance`, every sleep is recorded
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct MockClock {
+    now: std::sync::Mutex<Instant>,
+    sleeps: std::sync::Mutex<Vec<Duration>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(MockClock { now: std::sync::Mutex::new(Instant::now()), sleeps: std::sync::Mutex::new(Vec::new()) })
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    pub(crate) fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.sleeps.lock().unwrap().push(duration);
+        self.advance(duration);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{Cmd, RedisError};
+
+///Keyword flags accepted by command
+pub(crate) struct FlagSpec {
+    ///Flags in order they are encoded
+    pub(crate) order: &'static [&'static str],
+    ///Sets of flags which can't be combined
+    pub(crate) exclusive: &'static [&'static [&'static str]],
+}
+
+///Appends keyword flags to command in order of spec, rejecting mutually exclusive flags
+pub(crate) struct ArgWriter {
+    spec: &'static FlagSpec,
+    flags: Vec<(&'static str, Vec<String>)>,
+}
+
+impl ArgWriter {
+    pub(crate) fn new(spec: &'static FlagSpec) -> Self {
+        ArgWriter { spec, flags: Vec::new() }
+    }
+
+    ///Add flag without value if `enabled`
+    pub(crate) fn flag(mut self, name: &'static str, enabled: bool) -> Self {
+        debug_assert!(self.spec.order.contains(&name), "Unknown flag {}", name);
+        if enabled {
+            self.flags.push((name, Vec::new()));
+        }
+
+        self
+    }
+
+    ///Add flag followed by value if value is set
+    pub(crate) fn flag_value<T: ToString>(mut self, name: &'static str, value: Option<T>) -> Self {
+        debug_assert!(self.spec.order.contains(&name), "Unknown flag {}", name);
+        if let Some(value) = value {
+            self.flags.push((name, vec![value.to_string()]));
+        }
+
+        self
+    }
+
+    ///Add flag followed by several values if values are set
+    pub(crate) fn flag_values(mut self, name: &'static str, values: Option<Vec<String>>) -> Self {
+        debug_assert!(self.spec.order.contains(&name), "Unknown flag {}", name);
+        if let Some(values) = values {
+            self.flags.push((name, values));
+        }
+
+        self
+    }
+
+    ///Validate flags and append them to command
+    pub(crate) fn write(self, mut cmd: Cmd) -> Result<Cmd, RedisError> {
+        for exclusive in self.spec.exclusive {
+            let used: Vec<&str> = exclusive.iter()
+                .copied()
+                .filter(|name| self.flags.iter().any(|(flag, _)| flag == name))
+                .collect();
+
+            if used.len() > 1 {
+                return Err(RedisError::InvalidArgument(format!("Flags {} can't be combined", used.join(", "))));
+            }
+        }
+
+        for name in self.spec.order {
+            for (flag, values) in self.flags.iter().filter(|(flag, _)| flag == name) {
+                cmd = cmd.arg(flag);
+                for value in values {
+                    cmd = cmd.arg(value);
+                }
+            }
+        }
+
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArgWriter, FlagSpec};
+    use crate::{Cmd, RedisError};
+
+    static SPEC: FlagSpec = FlagSpec {
+        order: &["NX", "XX", "GET", "EX", "PX"],
+        exclusive: &[&["NX", "XX"], &["EX", "PX"]],
+    };
+
+    #[test]
+    fn flags_in_spec_order() {
+        let cmd = ArgWriter::new(&SPEC)
+            .flag_value("EX", Some(10))
+            .flag("GET", true)
+            .flag("XX", true)
+            .flag("NX", false)
+            .flag_value::<u64>("PX", None)
+            .write(Cmd::cmd("SET").arg("key").arg("value"))
+            .unwrap();
+
+        let args: Vec<String> = cmd.args.iter().map(|arg| String::from_utf8_lossy(arg).into_owned()).collect();
+        assert_eq!(vec!["SET", "key", "value", "XX", "GET", "EX", "10"], args);
+    }
+
+    #[test]
+    fn exclusive_flags_rejected() {
+        let result = ArgWriter::new(&SPEC)
+            .flag("NX", true)
+            .flag("XX", true)
+            .write(Cmd::cmd("SET"));
+
+        match result {
+            Err(RedisError::InvalidArgument(message)) => assert!(message.contains("NX, XX")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt;
+
+use crate::{Client, Cmd, FromRedisValue, HashFields, RedisError};
+
+use super::{check, expect_ok};
+
+///Credentials replayed by `reconnect`
+pub(crate) struct Credentials {
+    username: Option<String>,
+    password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials").field("username", &self.username).field("password", &"***").finish()
+    }
+}
+
+impl Credentials {
+    fn command(&self) -> Cmd {
+        match &self.username {
+            Some(username) => Cmd::cmd("AUTH").arg(username).arg(&self.password),
+            None => Cmd::cmd("AUTH").arg(&self.password),
+        }
+    }
+
+    ///`HELLO` switching protocol and authenticating in one round trip, `HELLO` requires username
+    fn hello(&self, version: u8) -> Cmd {
+        let username = self.username.as_deref().unwrap_or("default");
+
+        Cmd::cmd("HELLO").arg(&version.to_string()).arg("AUTH").arg(username).arg(&self.password)
+    }
+}
+
+impl Client {
+    ///Authenticate connection by password, it's repeated after reconnect
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.auth("secret").unwrap();
+    ///```
+    pub fn auth(&mut self, password: &str) -> Result<(), RedisError> {
+        self.authenticate(Credentials { username: None, password: password.to_string() })
+    }
+
+    ///Authenticate connection as ACL user, it's repeated after reconnect
+    pub fn auth_user(&mut self, username: &str, password: &str) -> Result<(), RedisError> {
+        self.authenticate(Credentials { username: Some(username.to_string()), password: password.to_string() })
+    }
+
+    ///Switch protocol version of connection by `HELLO`, returns properties of server
+    ///
+    ///Version is negotiated again after reconnect
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let mut server = client.hello(3).unwrap();
+    ///let version: String = server.take("version").unwrap();
+    ///```
+    pub fn hello(&mut self, version: u8) -> Result<HashFields, RedisError> {
+        let server = HashFields::from_redis_value(check(Cmd::cmd("HELLO").arg(&version.to_string()).run(self)?)?)?;
+        self.protocol = version;
+
+        Ok(server)
+    }
+
+    ///Switch protocol version and authenticate as ACL user in one round trip by `HELLO version AUTH username password`
+    ///
+    ///Rejected credentials fail with server error like `WRONGPASS` and leave protocol unchanged,
+    ///accepted ones are repeated by `AUTH` after reconnect
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let server = client.hello_auth(3, "app", "secret").unwrap();
+    ///```
+    pub fn hello_auth(&mut self, version: u8, username: &str, password: &str) -> Result<HashFields, RedisError> {
+        let reply = Cmd::cmd("HELLO").arg(&version.to_string()).arg("AUTH").arg(username).arg(password).run(self)?;
+        let server = HashFields::from_redis_value(check(reply)?)?;
+        self.credentials = Some(Credentials { username: Some(username.to_string()), password: password.to_string() });
+        self.protocol = version;
+
+        Ok(server)
+    }
+
+    ///Select database, it's selected again after reconnect
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.select(2).unwrap();
+    ///```
+    pub fn select(&mut self, db: i64) -> Result<(), RedisError> {
+        expect_ok(Cmd::cmd("SELECT").arg(&db.to_string()).run(self)?)?;
+        self.db = db;
+
+        Ok(())
+    }
+
+    ///Reconnect when connection is lost, authentication and database are restored
+    ///
+    ///Command is sent again only if it couldn't be written or it doesn't change data like `GET`,
+    ///otherwise it may have been executed already and its error is returned
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_auto_reconnect(true);
+    ///```
+    pub fn set_auto_reconnect(&mut self, enabled: bool) {
+        self.auto_reconnect = enabled;
+    }
+
+    fn authenticate(&mut self, credentials: Credentials) -> Result<(), RedisError> {
+        expect_ok(credentials.command().run(self)?)?;
+        self.credentials = Some(credentials);
+
+        Ok(())
+    }
+
+    ///Replay `HELLO` or `AUTH` and `SELECT` on new connection
+    pub(crate) fn restore_session(&mut self) -> Result<(), RedisError> {
+        //New connection starts with RESP2, `HELLO` authenticates too
+        if self.protocol != 2 {
+            let cmd = match &self.credentials {
+                Some(credentials) => credentials.hello(self.protocol),
+                None => Cmd::cmd("HELLO").arg(&self.protocol.to_string()),
+            };
+            check(self.execute_once(&Cmd::create_command(&cmd.args))?)?;
+        } else if let Some(credentials) = &self.credentials {
+            let bytes = Cmd::create_command(&credentials.command().args);
+            expect_ok(check(self.execute_once(&bytes)?)?)?;
+        }
+        if self.db != 0 {
+            let bytes = Cmd::create_command(&Cmd::cmd("SELECT").arg(&self.db.to_string()).args);
+            expect_ok(check(self.execute_once(&bytes)?)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::RedisError;
+
+    #[test]
+    fn restore_db_after_reconnect() {
+        let server = MockServer::sessions(&[&["+OK\r\n", "+OK\r\n"], &["+OK\r\n", "$5\r\nvalue\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+
+        client.select(2).unwrap();
+        client.set("key", "value").unwrap();
+        assert_eq!(Some(String::from("value")), client.get("key").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["SELECT", "2"], vec!["SET", "key", "value"], vec!["SELECT", "2"], vec!["GET", "key"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn replay_auth_before_select() {
+        let server = MockServer::sessions(&[&["+OK\r\n", "+OK\r\n"], &["+OK\r\n", "+OK\r\n", ":1\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+
+        client.auth_user("app", "secret").unwrap();
+        client.select(3).unwrap();
+        assert_eq!(1, client.llen("queue").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["AUTH", "app", "secret"],
+                vec!["SELECT", "3"],
+                vec!["AUTH", "app", "secret"],
+                vec!["SELECT", "3"],
+                vec!["LLEN", "queue"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn hello_auth_encodes_auth_clause() {
+        let server = MockServer::new(&["%1\r\n$5\r\nproto\r\n:3\r\n"]);
+        let mut client = server.client();
+
+        let mut properties = client.hello_auth(3, "app", "secret").unwrap();
+        assert_eq!(3, properties.take::<i64>("proto").unwrap());
+        assert!(client.credentials.is_some());
+        drop(client);
+        assert_eq!(vec![vec!["HELLO", "3", "AUTH", "app", "secret"]], server.commands());
+    }
+
+    #[test]
+    fn hello_auth_rejected() {
+        let server = MockServer::new(&["-WRONGPASS invalid username-password pair or user is disabled.\r\n"]);
+        let mut client = server.client();
+
+        match client.hello_auth(3, "app", "wrong") {
+            Err(RedisError::Server { code, .. }) => assert_eq!("WRONGPASS", code),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(client.credentials.is_none());
+    }
+
+    #[test]
+    fn replay_hello_after_reconnect() {
+        let hello = "%1\r\n$5\r\nproto\r\n:3\r\n";
+        let server = MockServer::sessions(&[&[hello], &[hello, "$5\r\nvalue\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+
+        client.hello_auth(3, "app", "secret").unwrap();
+        assert_eq!(Some(String::from("value")), client.get("key").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["HELLO", "3", "AUTH", "app", "secret"],
+                vec!["HELLO", "3", "AUTH", "app", "secret"],
+                vec!["GET", "key"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn replay_hello_with_password_after_reconnect() {
+        let hello = "%1\r\n$5\r\nproto\r\n:3\r\n";
+        let server = MockServer::sessions(&[&["+OK\r\n", hello], &[hello, "$-1\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+
+        client.auth("secret").unwrap();
+        client.hello(3).unwrap();
+        assert_eq!(None, client.get("key").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["AUTH", "secret"],
+                vec!["HELLO", "3"],
+                vec!["HELLO", "3", "AUTH", "default", "secret"],
+                vec!["GET", "key"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn written_command_not_sent_again() {
+        use std::{io::{BufReader, Write}, net::TcpListener, thread};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut dropped = BufReader::new(stream);
+            let mut commands = vec![crate::parse_response(&mut dropped).unwrap()];
+            drop(dropped);
+
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            commands.push(crate::parse_response(&mut reader).unwrap());
+            reader.get_mut().write_all(b":5\r\n").unwrap();
+
+            commands
+        });
+        let mut client = crate::Client::new(addr).unwrap();
+        client.set_auto_reconnect(true);
+
+        assert!(matches!(client.incr("counter"), Err(RedisError::Io(_))));
+        assert_eq!(5, client.incr("counter").unwrap());
+        let incr = || crate::Values::Arrays(vec![
+            crate::Values::BulkString(String::from("INCR")),
+            crate::Values::BulkString(String::from("counter")),
+        ]);
+        assert_eq!(vec![incr(), incr()], server.join().unwrap());
+    }
+
+    #[test]
+    fn no_retry_without_auto_reconnect() {
+        let server = MockServer::sessions(&[&["+OK\r\n"], &["$5\r\nvalue\r\n"]]);
+        let mut client = server.client();
+
+        client.set("key", "value").unwrap();
+        assert!(client.get("key").is_err());
+    }
+}
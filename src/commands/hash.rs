@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+use crate::{Client, Cmd, RedisError};
+
+use super::{into_array, into_integer, into_optional_string, into_string_map, into_string_pairs, into_strings};
+
+impl Client {
+    ///Set hash fields, returns count of added fields
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.hset("user:1", &[("name", "Bob"), ("age", "42")]).unwrap();
+    ///```
+    pub fn hset(&mut self, key: &str, fields: &[(&str, &str)]) -> Result<i64, RedisError> {
+        if fields.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("HSET requires at least one field")));
+        }
+
+        let cmd = fields.iter().fold(Cmd::cmd("HSET").key(key), |cmd, (field, value)| cmd.arg(field).arg(value));
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Get all fields and values of hash
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let user = client.hgetall("user:1");
+    ///```
+    pub fn hgetall(&mut self, key: &str) -> Result<HashMap<String, String>, RedisError> {
+        into_string_map(Cmd::cmd("HGETALL").key(key).run(self)?)
+    }
+
+    ///Get all fields and values of hash in order sent by server
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///for (field, value) in client.hgetall_vec("user:1").unwrap() {
+    ///    println!("{}: {}", field, value);
+    ///}
+    ///```
+    pub fn hgetall_vec(&mut self, key: &str) -> Result<Vec<(String, String)>, RedisError> {
+        into_string_pairs(Cmd::cmd("HGETALL").key(key).run(self)?)
+    }
+
+    ///Get count of hash fields, missing key is empty hash
+    pub fn hlen(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("HLEN").key(key).run(self)?)
+    }
+
+    ///Get all field names of hash
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let fields = client.hkeys("user:1");
+    ///```
+    pub fn hkeys(&mut self, key: &str) -> Result<Vec<String>, RedisError> {
+        into_strings(Cmd::cmd("HKEYS").key(key).run(self)?)
+    }
+
+    ///Get all values of hash
+    pub fn hvals(&mut self, key: &str) -> Result<Vec<String>, RedisError> {
+        into_strings(Cmd::cmd("HVALS").key(key).run(self)?)
+    }
+
+    ///Get values of hash fields, missing fields are `None`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let values = client.hmget("user:1", &["name", "email"]);
+    ///```
+    pub fn hmget(&mut self, key: &str, fields: &[&str]) -> Result<Vec<Option<String>>, RedisError> {
+        if fields.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("HMGET requires at least one field")));
+        }
+
+        into_array(Cmd::cmd("HMGET").key(key).args(fields).run(self)?)?
+            .into_iter()
+            .map(into_optional_string)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::into_string_map;
+    use crate::mock::MockServer;
+    use crate::{RedisError, Values};
+
+    #[test]
+    fn hmget_keeps_order_and_nils() {
+        let server = MockServer::new(&["*3\r\n$2\r\nv1\r\n$-1\r\n$2\r\nv3\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(
+            vec![Some(String::from("v1")), None, Some(String::from("v3"))],
+            client.hmget("hash", &["f1", "f2", "f3"]).unwrap()
+        );
+        drop(client);
+        assert_eq!(vec![vec!["HMGET", "hash", "f1", "f2", "f3"]], server.commands());
+    }
+
+    #[test]
+    fn hgetall_vec_keeps_server_order() {
+        let server = MockServer::new(&[
+            "*6\r\n$1\r\nz\r\n$1\r\n1\r\n$1\r\na\r\n$1\r\n2\r\n$1\r\nm\r\n$1\r\n3\r\n",
+            "*3\r\n$1\r\nz\r\n$1\r\n1\r\n$1\r\na\r\n",
+        ]);
+        let mut client = server.client();
+
+        assert_eq!(
+            vec![
+                (String::from("z"), String::from("1")),
+                (String::from("a"), String::from("2")),
+                (String::from("m"), String::from("3")),
+            ],
+            client.hgetall_vec("hash").unwrap()
+        );
+        assert!(matches!(client.hgetall_vec("broken"), Err(RedisError::Protocol(_))));
+    }
+
+    #[test]
+    fn hash_introspection() {
+        let server = MockServer::new(&[
+            ":2\r\n",
+            ":2\r\n",
+            "*2\r\n$4\r\nname\r\n$3\r\nage\r\n",
+            "*2\r\n$3\r\nBob\r\n$2\r\n42\r\n",
+        ]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.hset("user", &[("name", "Bob"), ("age", "42")]).unwrap());
+        assert_eq!(2, client.hlen("user").unwrap());
+        assert_eq!(vec!["name", "age"], client.hkeys("user").unwrap());
+        assert_eq!(vec!["Bob", "42"], client.hvals("user").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["HSET", "user", "name", "Bob", "age", "42"],
+                vec!["HLEN", "user"],
+                vec!["HKEYS", "user"],
+                vec!["HVALS", "user"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn empty_hash_introspection() {
+        let server = MockServer::new(&[":0\r\n", "*0\r\n", "*0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(0, client.hlen("missing").unwrap());
+        assert!(client.hkeys("missing").unwrap().is_empty());
+        assert!(client.hvals("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn hgetall_into_map() {
+        let server = MockServer::new(&["*4\r\n$4\r\nname\r\n$3\r\nBob\r\n$3\r\nage\r\n$2\r\n42\r\n"]);
+        let mut client = server.client();
+
+        let user = client.hgetall("user").unwrap();
+        assert_eq!(2, user.len());
+        assert_eq!("Bob", user["name"]);
+        assert_eq!("42", user["age"]);
+    }
+
+    #[test]
+    fn hgetall_of_integer_reply() {
+        match into_string_map(Values::Integers(1)) {
+            Err(RedisError::UnexpectedReply { expected, got, .. }) => assert_eq!(("array", "integer"), (expected, got)),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unexpected_reply_message_has_reply() {
+        let error = into_string_map(Values::BulkString(String::from("user:1"))).unwrap_err();
+        assert_eq!("expected array reply, got bulk string: BulkString(\"user:1\")", error.to_string());
+
+        let error = into_string_map(Values::BulkString("x".repeat(1000))).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("BulkString(\"xxx"));
+        assert!(message.ends_with("..."));
+        assert!(message.len() < 200);
+    }
+
+    #[test]
+    fn hmget_without_fields() {
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+
+        assert!(matches!(client.hmget("hash", &[]), Err(RedisError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Client, Cmd, RedisError};
+
+use super::{expect_ok, into_optional_bytes};
+
+impl Client {
+    ///Store value serialized to JSON string
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_json("settings", &vec!["dark", "compact"]).unwrap();
+    ///```
+    pub fn set_json<T: Serialize>(&mut self, key: &str, value: &T) -> Result<(), RedisError> {
+        let json = serde_json::to_vec(value)
+            .map_err(|e| RedisError::InvalidArgument(format!("Value can't be serialized to JSON: {}", e)))?;
+
+        expect_ok(Cmd::cmd("SET").key(key).arg_bytes(&json).run(self)?)
+    }
+
+    ///Get value stored by `set_json`, `None` if key is missing
+    ///
+    ///Value which isn't valid JSON of `T` fails with `RedisError::Deserialize`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let settings: Option<Vec<String>> = client.get_json("settings").unwrap();
+    ///```
+    pub fn get_json<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<T>, RedisError> {
+        match into_optional_bytes(Cmd::cmd("GET").key(key).run(self)?)? {
+            Some(json) => serde_json::from_slice(&json).map(Some).map_err(RedisError::Deserialize),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::mock::MockServer;
+    use crate::RedisError;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = r#"{"name":"Bob","age":42}"#;
+        let server = MockServer::new(&["+OK\r\n", &format!("${}\r\n{}\r\n", json.len(), json), "$-1\r\n"]);
+        let mut client = server.client();
+        let user = User { name: String::from("Bob"), age: 42 };
+
+        client.set_json("user:1", &user).unwrap();
+        assert_eq!(Some(user), client.get_json("user:1").unwrap());
+        assert_eq!(None, client.get_json::<User>("user:2").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["SET", "user:1", json], vec!["GET", "user:1"], vec!["GET", "user:2"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn invalid_json_fails_to_deserialize() {
+        let server = MockServer::new(&["$3\r\nBob\r\n"]);
+        let mut client = server.client();
+
+        assert!(matches!(client.get_json::<User>("user:1"), Err(RedisError::Deserialize(_))));
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn json_round_trip_on_server() {
+        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+        let user = User { name: String::from("Alice"), age: 30 };
+
+        client.set_json("json_round_trip", &user).unwrap();
+        assert_eq!(Some(user), client.get_json("json_round_trip").unwrap());
+    }
+}
@@ -0,0 +1,717 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use crate::{hooks, Client, Cmd, FromRedisValue, RedisError, Values};
+
+use super::args::{ArgWriter, FlagSpec};
+use super::{expect_ok, into_array, into_integer, into_optional_string, into_strings};
+
+///Commands which never change keys, they keep cached types of their keys
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "TYPE", "EXISTS", "GET", "MGET", "STRLEN", "GETRANGE", "TTL", "PTTL", "EXPIRETIME", "OBJECT", "DUMP", "LLEN",
+    "LRANGE", "LINDEX", "HGET", "HMGET", "HGETALL", "HLEN", "HEXISTS", "HKEYS", "HVALS", "SCARD", "SMEMBERS",
+    "SISMEMBER", "ZCARD", "ZSCORE", "ZRANGE", "ZRANK", "XLEN", "XRANGE", "MEMORY",
+];
+
+///Commands without keys which don't change anything either, safe to send again
+const SERVER_READ_COMMANDS: &[&str] = &["PING", "ECHO", "INFO", "COMMAND", "DBSIZE", "SCAN", "KEYS", "TIME"];
+
+///Command doesn't change data, so it can be sent again when its reply is lost
+pub(crate) fn is_read_only(command: &str) -> bool {
+    READ_ONLY_COMMANDS.iter().chain(SERVER_READ_COMMANDS).any(|read_only| read_only.eq_ignore_ascii_case(command))
+}
+
+///Commands changing whole database, they drop all cached types
+const DATABASE_COMMANDS: &[&str] = &["SELECT", "FLUSHDB", "FLUSHALL", "SWAPDB"];
+
+static EXPIRE_FLAGS: FlagSpec = FlagSpec {
+    order: &["NX", "XX", "GT", "LT"],
+    exclusive: &[&["NX", "XX"], &["NX", "GT"], &["NX", "LT"], &["GT", "LT"]],
+};
+
+///Conditions of `EXPIRE` command
+///```
+///use redis_client::ExpireOptions;
+///
+///let options = ExpireOptions::new().xx().gt();
+///```
+#[derive(Debug, Default, Clone)]
+pub struct ExpireOptions {
+    nx: bool,
+    xx: bool,
+    gt: bool,
+    lt: bool,
+}
+
+impl ExpireOptions {
+    pub fn new() -> Self {
+        ExpireOptions::default()
+    }
+
+    ///Set expiry only if key has none
+    pub fn nx(mut self) -> Self {
+        self.nx = true;
+
+        self
+    }
+
+    ///Set expiry only if key already has one
+    pub fn xx(mut self) -> Self {
+        self.xx = true;
+
+        self
+    }
+
+    ///Set expiry only if it's later than current one
+    pub fn gt(mut self) -> Self {
+        self.gt = true;
+
+        self
+    }
+
+    ///Set expiry only if it's earlier than current one
+    pub fn lt(mut self) -> Self {
+        self.lt = true;
+
+        self
+    }
+
+    fn write(&self, cmd: Cmd) -> Result<Cmd, RedisError> {
+        ArgWriter::new(&EXPIRE_FLAGS)
+            .flag("NX", self.nx)
+            .flag("XX", self.xx)
+            .flag("GT", self.gt)
+            .flag("LT", self.lt)
+            .write(cmd)
+    }
+}
+
+///Key whose internal encoding is sampled after commands touching it
+#[derive(Debug)]
+pub(crate) struct EncodingWatch {
+    key: String,
+    encoding: Option<String>,
+    observer: hooks::EncodingObserver,
+}
+
+///Replies of `TYPE` kept for `ttl`
+#[derive(Debug)]
+pub(crate) struct TypeCache {
+    ttl: Duration,
+    entries: HashMap<String, (RedisType, Instant)>,
+}
+
+impl TypeCache {
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+///Type of value stored at key, returned by `TYPE`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RedisType {
+    ///Key is missing
+    None,
+    String,
+    List,
+    Set,
+    ZSet,
+    Hash,
+    Stream,
+    ///Type unknown to this client, e.g. added by module
+    Other(String),
+}
+
+impl FromRedisValue for RedisType {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        let name = String::from_redis_value(value)?;
+
+        Ok(match name.as_str() {
+            "none" => RedisType::None,
+            "string" => RedisType::String,
+            "list" => RedisType::List,
+            "set" => RedisType::Set,
+            "zset" => RedisType::ZSet,
+            "hash" => RedisType::Hash,
+            "stream" => RedisType::Stream,
+            _ => RedisType::Other(name),
+        })
+    }
+}
+
+///Internal encoding of value, returned by `OBJECT ENCODING`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Encoding {
+    ///String holding 64 bit integer
+    Int,
+    ///Short string allocated with its object
+    EmbStr,
+    Raw,
+    ///Compact encoding of small lists, hashes, sets and sorted sets
+    ListPack,
+    QuickList,
+    ///Set of integers only
+    IntSet,
+    HashTable,
+    SkipList,
+    Stream,
+    ///Encoding unknown to this client, e.g. `ziplist` of old servers
+    Other(String),
+}
+
+impl FromRedisValue for Encoding {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        let name = String::from_redis_value(value)?;
+
+        Ok(match name.as_str() {
+            "int" => Encoding::Int,
+            "embstr" => Encoding::EmbStr,
+            "raw" => Encoding::Raw,
+            "listpack" => Encoding::ListPack,
+            "quicklist" => Encoding::QuickList,
+            "intset" => Encoding::IntSet,
+            "hashtable" => Encoding::HashTable,
+            "skiplist" => Encoding::SkipList,
+            "stream" => Encoding::Stream,
+            _ => Encoding::Other(name),
+        })
+    }
+}
+
+impl Client {
+    ///Get all keys matching pattern, it blocks server while scanning whole keyspace so use `SCAN` in production
+    ///
+    ///Reply longer than keys limit is rejected
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let keys = client.keys("user:*");
+    ///```
+    pub fn keys(&mut self, pattern: &str) -> Result<Vec<String>, RedisError> {
+        self.warn("KEYS blocks server while scanning whole keyspace, use SCAN instead");
+
+        let keys = into_strings(Cmd::cmd("KEYS").arg(pattern).run(self)?)?;
+        if keys.len() > self.keys_limit {
+            return Err(RedisError::ReplyTooLarge { limit: self.keys_limit, len: keys.len() });
+        }
+
+        Ok(keys)
+    }
+
+    ///Set max count of keys accepted from `keys`, default is 10 000
+    pub fn set_keys_limit(&mut self, limit: usize) {
+        self.keys_limit = limit;
+    }
+
+    ///Iterate keys matching pattern by `SCAN` passing them to callback in batches of `batch` keys, last batch may be shorter
+    ///
+    ///Only one batch is held in memory, key changed during scan may be passed twice. Error of callback stops scan
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///let mut other = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.scan_process("session:*", 100, |keys| {
+    ///    for key in keys {
+    ///        other.expire(key, 3600)?;
+    ///    }
+    ///
+    ///    Ok(())
+    ///}).unwrap();
+    ///```
+    pub fn scan_process<F>(&mut self, pattern: &str, batch: usize, mut f: F) -> Result<(), RedisError>
+    where
+        F: FnMut(&[String]) -> Result<(), RedisError>,
+    {
+        if batch == 0 {
+            return Err(RedisError::InvalidArgument(String::from("Batch size must be positive")));
+        }
+
+        let mut cursor = String::from("0");
+        let mut keys = Vec::with_capacity(batch);
+        loop {
+            let cmd = Cmd::cmd("SCAN").arg(&cursor).arg("MATCH").arg(pattern).arg("COUNT").arg(&batch.to_string());
+            let mut reply = into_array(cmd.run(self)?)?.into_iter();
+            let (next, page) = match (reply.next(), reply.next(), reply.next()) {
+                (Some(next), Some(page), None) => (String::from_redis_value(next)?, into_strings(page)?),
+                _ => return Err(RedisError::Protocol(String::from("SCAN reply must have cursor and keys"))),
+            };
+
+            for key in page {
+                keys.push(key);
+                if keys.len() == batch {
+                    f(&keys)?;
+                    keys.clear();
+                }
+            }
+
+            if next == "0" {
+                break;
+            }
+            cursor = next;
+        }
+
+        if !keys.is_empty() {
+            f(&keys)?;
+        }
+
+        Ok(())
+    }
+
+    ///Rename key, existing `newkey` is overwritten
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.rename("old", "new").unwrap();
+    ///```
+    pub fn rename(&mut self, key: &str, newkey: &str) -> Result<(), RedisError> {
+        expect_ok(Cmd::cmd("RENAME").key(key).key(newkey).run(self)?)
+    }
+
+    ///Copy value of key to `dst` in current database, returns `false` if `src` is missing or `dst` exists
+    pub fn copy(&mut self, src: &str, dst: &str) -> Result<bool, RedisError> {
+        self.copy_opts(src, dst, None, false)
+    }
+
+    ///Copy value of key to `dst` in database `db`, current one when `None`, `replace` overwrites existing `dst`
+    ///
+    ///Returns `false` if `src` is missing or `dst` exists without `replace`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let copied = client.copy_opts("user:1", "user:1", Some(2), true).unwrap();
+    ///```
+    pub fn copy_opts(&mut self, src: &str, dst: &str, db: Option<u8>, replace: bool) -> Result<bool, RedisError> {
+        if src == dst && db.map(i64::from).unwrap_or(self.db) == self.db {
+            return Err(RedisError::InvalidArgument(String::from("COPY source and destination are same key")));
+        }
+
+        let mut cmd = Cmd::cmd("COPY").key(src).key(dst);
+        if let Some(db) = db {
+            cmd = cmd.arg("DB").arg(&db.to_string());
+        }
+        if replace {
+            cmd = cmd.arg("REPLACE");
+        }
+
+        Ok(into_integer(cmd.run(self)?)? == 1)
+    }
+
+    ///Get type of value stored at key
+    ///```no_run
+    ///use redis_client::{Client, RedisType};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///if client.key_type("queue").unwrap() == RedisType::List {
+    ///    println!("queue is list");
+    ///}
+    ///```
+    pub fn key_type(&mut self, key: &str) -> Result<RedisType, RedisError> {
+        if let Some(cache) = &self.type_cache {
+            match cache.entries.get(key) {
+                Some((key_type, cached_at)) if self.clock.now() - *cached_at < cache.ttl => return Ok(key_type.clone()),
+                _ => {},
+            }
+        }
+
+        let key_type = RedisType::from_redis_value(Cmd::cmd("TYPE").key(key).run(self)?)?;
+        let now = self.clock.now();
+        if let Some(cache) = &mut self.type_cache {
+            cache.entries.insert(key.to_string(), (key_type.clone(), now));
+        }
+
+        Ok(key_type)
+    }
+
+    ///Keep replies of `key_type` for `ttl` so repeated checks of same key skip `TYPE`, `None` turns cache off
+    ///
+    ///Cached type of key is dropped when this client sends command which may change key,
+    ///command with keys passed by `arg` instead of `Cmd::key` drops whole cache.
+    ///Changes made by other clients are seen only after `ttl`
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_type_cache_ttl(Some(Duration::from_secs(1)));
+    ///```
+    pub fn set_type_cache_ttl(&mut self, ttl: Option<Duration>) {
+        self.type_cache = ttl.map(|ttl| TypeCache { ttl, entries: HashMap::new() });
+    }
+
+    ///Drop cached types of keys command may change
+    pub(crate) fn forget_types(&mut self, cmd: &Cmd) {
+        let cache = match &mut self.type_cache {
+            Some(cache) if !cache.entries.is_empty() => cache,
+            _ => return,
+        };
+
+        let command = String::from_utf8_lossy(&cmd.args[0]).to_uppercase();
+        if is_read_only(&command) {
+            return;
+        }
+
+        //Keys of command built by `arg` aren't known, any of its arguments may be changed key
+        if DATABASE_COMMANDS.contains(&command.as_str()) || cmd.keys.is_empty() {
+            cache.entries.clear();
+        } else {
+            for &position in &cmd.keys {
+                cache.entries.remove(String::from_utf8_lossy(&cmd.args[position]).as_ref());
+            }
+        }
+    }
+
+    ///Get internal encoding of value, `None` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let encoding = client.object_encoding("key");
+    ///```
+    pub fn object_encoding(&mut self, key: &str) -> Result<Option<String>, RedisError> {
+        into_optional_string(Cmd::cmd("OBJECT").arg("ENCODING").key(key).run(self)?)
+    }
+
+    ///Get internal encoding of value as `Encoding`, `None` if key is missing
+    ///```no_run
+    ///use redis_client::{Client, Encoding};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///if client.encoding("user:1").unwrap() == Some(Encoding::HashTable) {
+    ///    println!("user:1 outgrew listpack");
+    ///}
+    ///```
+    pub fn encoding(&mut self, key: &str) -> Result<Option<Encoding>, RedisError> {
+        Option::from_redis_value(Cmd::cmd("OBJECT").arg("ENCODING").key(key).run(self)?)
+    }
+
+    ///Call `f` with new encoding whenever command of this client touching `key` changes its internal encoding
+    ///
+    ///Diagnostic only, every such command costs extra `OBJECT ENCODING` round trip
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.watch_encoding("user:1", |encoding| println!("user:1 is {} now", encoding)).unwrap();
+    ///```
+    pub fn watch_encoding<F: Fn(&str) + Send + 'static>(&mut self, key: &str, f: F) -> Result<(), RedisError> {
+        let encoding = self.object_encoding(key)?;
+        self.encoding_watches.push(EncodingWatch { key: key.to_string(), encoding, observer: hooks::Hook(Box::new(f)) });
+
+        Ok(())
+    }
+
+    ///Sample encodings of watched keys touched by command
+    pub(crate) fn observe_encodings(&mut self, cmd: &Cmd) {
+        for index in 0..self.encoding_watches.len() {
+            if !cmd.has_key(&self.encoding_watches[index].key) {
+                continue;
+            }
+
+            let sample = Cmd::cmd("OBJECT").arg("ENCODING").key(&self.encoding_watches[index].key);
+            let bytes = sample.encode(self);
+            let encoding = match self.execute_once(&bytes).and_then(into_optional_string) {
+                Ok(encoding) => encoding,
+                Err(_) => continue,
+            };
+
+            let watch = &mut self.encoding_watches[index];
+            if encoding != watch.encoding {
+                if let Some(encoding) = &encoding {
+                    (watch.observer.0)(encoding);
+                }
+                watch.encoding = encoding;
+            }
+        }
+    }
+
+    ///Set time to live of key in seconds, returns `false` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.expire("session", 600).unwrap();
+    ///```
+    pub fn expire(&mut self, key: &str, seconds: i64) -> Result<bool, RedisError> {
+        self.expire_options(key, seconds, &ExpireOptions::new())
+    }
+
+    ///Set time to live of key in seconds if conditions are met
+    pub fn expire_options(&mut self, key: &str, seconds: i64, options: &ExpireOptions) -> Result<bool, RedisError> {
+        let cmd = options.write(Cmd::cmd("EXPIRE").key(key).arg(&seconds.to_string()))?;
+
+        Ok(into_integer(cmd.run(self)?)? == 1)
+    }
+
+    ///Get remaining time to live of key in seconds, `-1` if key has no expiry and `-2` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let ttl = client.ttl("key");
+    ///```
+    pub fn ttl(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("TTL").key(key).run(self)?)
+    }
+
+    ///Get remaining time to live of key in milliseconds, sentinels same as `ttl`
+    pub fn pttl(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("PTTL").key(key).run(self)?)
+    }
+
+    ///Get absolute Unix time in seconds when key expires, `-1` if key has no expiry and `-2` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let expire_at = client.expiretime("key");
+    ///```
+    pub fn expiretime(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("EXPIRETIME").key(key).run(self)?)
+    }
+
+    ///Get absolute Unix time in milliseconds when key expires, sentinels same as `expiretime`
+    pub fn pexpiretime(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("PEXPIRETIME").key(key).run(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::mock::MockServer;
+    use crate::{Client, Cmd, Encoding, ExpireOptions, RedisError, RedisType};
+
+    #[test]
+    fn expire_with_conditions() {
+        let server = MockServer::new(&[":1\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert!(client.expire("key", 10).unwrap());
+        assert!(!client.expire_options("key", 20, &ExpireOptions::new().gt().xx()).unwrap());
+        assert!(client.expire_options("key", 20, &ExpireOptions::new().nx().gt()).is_err());
+        drop(client);
+        assert_eq!(vec![vec!["EXPIRE", "key", "10"], vec!["EXPIRE", "key", "20", "XX", "GT"]], server.commands());
+    }
+
+    #[test]
+    fn keys_with_warning() {
+        let server = MockServer::new(&["*2\r\n$5\r\nuser1\r\n$5\r\nuser2\r\n"]);
+        let mut client = server.client();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let logged = Arc::clone(&warnings);
+        client.set_logger(move |message| logged.lock().unwrap().push(message.to_string()));
+
+        assert_eq!(vec!["user1", "user2"], client.keys("user*").unwrap());
+        assert!(warnings.lock().unwrap()[0].contains("SCAN"));
+    }
+
+    #[test]
+    fn scan_process_in_batches() {
+        let server = MockServer::new(&[
+            "*2\r\n$2\r\n17\r\n*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n",
+            "*2\r\n$1\r\n0\r\n*0\r\n",
+        ]);
+        let mut client = server.client();
+        let mut batches = Vec::new();
+
+        client.scan_process("*", 2, |keys| {
+            batches.push(keys.to_vec());
+
+            Ok(())
+        }).unwrap();
+        assert_eq!(vec![vec!["a", "b"], vec!["c"]], batches);
+        drop(client);
+        assert_eq!(
+            vec![vec!["SCAN", "0", "MATCH", "*", "COUNT", "2"], vec!["SCAN", "17", "MATCH", "*", "COUNT", "2"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn scan_process_counts_keys() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        for i in 0..1000 {
+            client.set(&format!("test_scan_process:{}", i), "1").unwrap();
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        client.scan_process("test_scan_process:*", 100, |keys| {
+            assert!(keys.len() <= 100);
+            seen.extend(keys.iter().cloned());
+
+            Ok(())
+        }).unwrap();
+        assert_eq!(1000, seen.len());
+    }
+
+    #[test]
+    fn keys_over_limit() {
+        let server = MockServer::new(&["*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n"]);
+        let mut client = server.client();
+        client.set_keys_limit(2);
+
+        match client.keys("*") {
+            Err(RedisError::ReplyTooLarge { limit, len }) => assert_eq!((2, 3), (limit, len)),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rename_key() {
+        let server = MockServer::new(&["+OK\r\n"]);
+        let mut client = server.client();
+
+        client.rename("old", "new").unwrap();
+        drop(client);
+        assert_eq!(vec![vec!["RENAME", "old", "new"]], server.commands());
+    }
+
+    #[test]
+    fn copy_with_and_without_db() {
+        let server = MockServer::new(&[":1\r\n", ":0\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        assert!(client.copy("user:1", "user:2").unwrap());
+        assert!(!client.copy_opts("user:1", "user:1", Some(3), false).unwrap());
+        assert!(client.copy_opts("user:1", "backup", Some(0), true).unwrap());
+        assert!(matches!(client.copy_opts("user:1", "user:1", Some(0), true), Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(client.copy("user:1", "user:1"), Err(RedisError::InvalidArgument(_))));
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["COPY", "user:1", "user:2"],
+                vec!["COPY", "user:1", "user:1", "DB", "3"],
+                vec!["COPY", "user:1", "backup", "DB", "0", "REPLACE"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn encoding_variants() {
+        let server = MockServer::new(&["$6\r\nembstr\r\n", "$8\r\nlistpack\r\n", "$7\r\nziplist\r\n", "$-1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(Encoding::EmbStr), client.encoding("name").unwrap());
+        assert_eq!(Some(Encoding::ListPack), client.encoding("list").unwrap());
+        assert_eq!(Some(Encoding::Other(String::from("ziplist"))), client.encoding("old").unwrap());
+        assert_eq!(None, client.encoding("missing").unwrap());
+        drop(client);
+        assert_eq!(vec!["OBJECT", "ENCODING", "name"], server.commands()[0]);
+    }
+
+    #[test]
+    fn key_type_variants() {
+        let server = MockServer::new(&["+string\r\n", "+none\r\n", "+stream\r\n", "+ReJSON-RL\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(RedisType::String, client.key_type("name").unwrap());
+        assert_eq!(RedisType::None, client.key_type("missing").unwrap());
+        assert_eq!(RedisType::Stream, client.key_type("events").unwrap());
+        assert_eq!(RedisType::Other(String::from("ReJSON-RL")), client.key_type("doc").unwrap());
+        drop(client);
+        assert_eq!(vec!["TYPE", "name"], server.commands()[0]);
+    }
+
+    #[test]
+    fn cached_type_skips_query_until_key_changes() {
+        let server = MockServer::new(&["+list\r\n", ":2\r\n", "+list\r\n"]);
+        let mut client = server.client();
+        client.set_type_cache_ttl(Some(std::time::Duration::from_secs(60)));
+
+        assert_eq!(RedisType::List, client.key_type("queue").unwrap());
+        assert_eq!(RedisType::List, client.key_type("queue").unwrap());
+        Cmd::cmd("LPUSH").key("queue").arg("job").execute(&mut client).unwrap();
+        assert_eq!(RedisType::List, client.key_type("queue").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["TYPE", "queue"], vec!["LPUSH", "queue", "job"], vec!["TYPE", "queue"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn command_without_declared_keys_drops_cached_types() {
+        let server = MockServer::new(&["+string\r\n", "+PONG\r\n", ":1\r\n", "+none\r\n"]);
+        let mut client = server.client();
+        client.set_type_cache_ttl(Some(std::time::Duration::from_secs(60)));
+
+        assert_eq!(RedisType::String, client.key_type("name").unwrap());
+        Cmd::cmd("PING").execute(&mut client).unwrap();
+        assert_eq!(RedisType::String, client.key_type("name").unwrap());
+        Cmd::cmd("DEL").arg("name").execute(&mut client).unwrap();
+        assert_eq!(RedisType::None, client.key_type("name").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["TYPE", "name"], vec!["PING"], vec!["DEL", "name"], vec!["TYPE", "name"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn encoding_change_observed() {
+        let server = MockServer::new(&["$8\r\nlistpack\r\n", ":1\r\n", "$8\r\nlistpack\r\n", ":1\r\n", "$9\r\nhashtable\r\n"]);
+        let mut client = server.client();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&changes);
+
+        client.watch_encoding("hash", move |encoding| observed.lock().unwrap().push(encoding.to_string())).unwrap();
+        client.hset("hash", &[("a", "1")]).unwrap();
+        client.hset("hash", &[("b", "long value")]).unwrap();
+
+        assert_eq!(vec!["hashtable"], *changes.lock().unwrap());
+        drop(client);
+        assert_eq!(vec!["OBJECT", "ENCODING", "hash"], server.commands()[4]);
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn hash_grows_past_listpack() {
+        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+        crate::Cmd::cmd("DEL").arg("test_watched_hash").execute(&mut client).unwrap();
+        client.hset("test_watched_hash", &[("field", "value")]).unwrap();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let observed = Arc::clone(&changes);
+
+        client.watch_encoding("test_watched_hash", move |encoding| observed.lock().unwrap().push(encoding.to_string())).unwrap();
+        for i in 0..200 {
+            client.hset("test_watched_hash", &[(&format!("field{}", i), "value")]).unwrap();
+        }
+
+        assert_eq!(vec!["hashtable"], *changes.lock().unwrap());
+    }
+
+    #[test]
+    fn object_encoding_of_missing_key() {
+        let server = MockServer::new(&["$8\r\nlistpack\r\n", "$-1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(String::from("listpack")), client.object_encoding("list").unwrap());
+        assert_eq!(None, client.object_encoding("missing").unwrap());
+    }
+
+    #[test]
+    fn expiretime_of_key_with_expiry() {
+        let server = MockServer::new(&[":1893456000\r\n", ":1893456000000\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(1893456000, client.expiretime("key").unwrap());
+        assert_eq!(1893456000000, client.pexpiretime("key").unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["EXPIRETIME", "key"], vec!["PEXPIRETIME", "key"]], server.commands());
+    }
+
+    #[test]
+    fn expiretime_of_missing_key() {
+        let server = MockServer::new(&[":-2\r\n", ":-2\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(-2, client.expiretime("missing").unwrap());
+        assert_eq!(-2, client.pexpiretime("missing").unwrap());
+    }
+}
@@ -0,0 +1,260 @@
+use std::{convert::TryFrom, time::Duration};
+
+use crate::{Client, Cmd, FromRedisValue, RedisError, Values};
+
+use super::{check, into_integer, into_strings};
+
+///Added to timeout of blocking pop for read timeout of connection, so reply sent at end of timeout still arrives
+const BLOCKING_TIMEOUT_MARGIN: Duration = Duration::from_millis(500);
+
+impl Client {
+    ///Get length of list, missing key is empty list
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let len = client.llen("queue");
+    ///```
+    pub fn llen(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("LLEN").key(key).run(self)?)
+    }
+
+    ///Prepend values to list, returns length of list after push
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.lpush("queue", &["first", "second"]).unwrap();
+    ///```
+    pub fn lpush(&mut self, key: &str, values: &[&str]) -> Result<i64, RedisError> {
+        self.push("LPUSH", key, values)
+    }
+
+    ///Append values to list, returns length of list after push
+    pub fn rpush(&mut self, key: &str, values: &[&str]) -> Result<i64, RedisError> {
+        self.push("RPUSH", key, values)
+    }
+
+    ///Prepend values only if list already exists, returns length of list after push or `0` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let len = client.lpushx("queue", &["first"]).unwrap();
+    ///```
+    pub fn lpushx(&mut self, key: &str, values: &[&str]) -> Result<i64, RedisError> {
+        self.push("LPUSHX", key, values)
+    }
+
+    ///Append values only if list already exists, returns length of list after push or `0` if key is missing
+    pub fn rpushx(&mut self, key: &str, values: &[&str]) -> Result<i64, RedisError> {
+        self.push("RPUSHX", key, values)
+    }
+
+    fn push(&mut self, command: &str, key: &str, values: &[&str]) -> Result<i64, RedisError> {
+        if values.is_empty() {
+            return Err(RedisError::InvalidArgument(format!("{} requires at least one value", command)));
+        }
+
+        into_integer(Cmd::cmd(command).key(key).args(values).run(self)?)
+    }
+
+    ///Pop first element of first non empty list, waits up to `timeout` seconds, returns key of list with element
+    ///or `None` on timeout
+    ///
+    ///Read timeout of connection is raised by `timeout` while waiting, zero `timeout` waits forever so it needs
+    ///connection without read timeout
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///if let Some((queue, job)) = client.blpop(&["jobs:high", "jobs:low"], 5.0).unwrap() {
+    ///    println!("{} from {}", job, queue);
+    ///}
+    ///```
+    pub fn blpop(&mut self, keys: &[&str], timeout: f64) -> Result<Option<(String, String)>, RedisError> {
+        self.blocking_pop("BLPOP", keys, timeout)
+    }
+
+    ///Pop last element of first non empty list, waits up to `timeout` seconds like `blpop`
+    pub fn brpop(&mut self, keys: &[&str], timeout: f64) -> Result<Option<(String, String)>, RedisError> {
+        self.blocking_pop("BRPOP", keys, timeout)
+    }
+
+    fn blocking_pop(&mut self, command: &str, keys: &[&str], timeout: f64) -> Result<Option<(String, String)>, RedisError> {
+        if keys.is_empty() {
+            return Err(RedisError::InvalidArgument(format!("{} requires at least one key", command)));
+        }
+        if !timeout.is_finite() || timeout < 0.0 {
+            return Err(RedisError::InvalidArgument(format!("{} timeout must be non negative, got {}", command, timeout)));
+        }
+
+        let extra = Duration::try_from_secs_f64(timeout).ok()
+            .and_then(|timeout| timeout.checked_add(BLOCKING_TIMEOUT_MARGIN))
+            .ok_or_else(|| RedisError::InvalidArgument(format!("{} timeout {} is too large", command, timeout)))?;
+        let cmd = keys.iter().fold(Cmd::cmd(command), |cmd, key| cmd.key(key)).arg(&timeout.to_string());
+
+        match check(self.with_blocking_timeout(extra, |client| cmd.run(client))?)? {
+            Values::Nil => Ok(None),
+            Values::Arrays(parts) => match <[Values; 2]>::try_from(parts) {
+                Ok([key, value]) => Ok(Some((String::from_redis_value(key)?, String::from_redis_value(value)?))),
+                Err(parts) => Err(RedisError::Protocol(format!("{} reply must have key and value, got {:?}", command, parts))),
+            },
+            other => Err(RedisError::unexpected_reply("array", &other)),
+        }
+    }
+
+    ///Get list elements from `start` to `stop` inclusive, negative indexes count from end
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let all = client.lrange("queue", 0, -1);
+    ///```
+    pub fn lrange(&mut self, key: &str, start: i64, stop: i64) -> Result<Vec<String>, RedisError> {
+        into_strings(Cmd::cmd("LRANGE").key(key).arg(&start.to_string()).arg(&stop.to_string()).run(self)?)
+    }
+
+    ///Read list in ranges of `chunk` elements passing each range to callback, so whole list isn't held in memory
+    ///
+    ///List changed meanwhile may give shorter or overlapping ranges
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.lrange_chunked("events", 100, |events| println!("{} events", events.len())).unwrap();
+    ///```
+    pub fn lrange_chunked<F: FnMut(&[String])>(&mut self, key: &str, chunk: i64, mut f: F) -> Result<(), RedisError> {
+        if chunk <= 0 {
+            return Err(RedisError::InvalidArgument(String::from("Chunk size must be positive")));
+        }
+
+        let len = self.llen(key)?;
+        let mut start = 0;
+        while start < len {
+            let elements = self.lrange(key, start, start + chunk - 1)?;
+            if elements.is_empty() {
+                break;
+            }
+
+            f(&elements);
+            start += chunk;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{Client, Cmd, RedisError};
+
+    #[test]
+    fn blpop_returns_source_key() {
+        let server = MockServer::new(&["*2\r\n$4\r\nlist\r\n$3\r\nval\r\n", "*-1\r\n", "_\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some((String::from("list"), String::from("val"))), client.blpop(&["empty", "list"], 1.0).unwrap());
+        assert_eq!(None, client.blpop(&["empty"], 0.5).unwrap());
+        assert_eq!(None, client.brpop(&["empty"], 0.5).unwrap());
+        assert!(matches!(client.blpop(&[], 1.0), Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(client.blpop(&["list"], -1.0), Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(client.blpop(&["list"], 1e20), Err(RedisError::InvalidArgument(_))));
+        drop(client);
+        assert_eq!(
+            vec![vec!["BLPOP", "empty", "list", "1"], vec!["BLPOP", "empty", "0.5"], vec!["BRPOP", "empty", "0.5"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn push_onto_string_key() {
+        let wrong_type = "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+        let server = MockServer::new(&[":2\r\n", wrong_type, wrong_type]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.rpush("queue", &["a", "b"]).unwrap());
+        match client.lpush("name", &["a"]) {
+            Err(RedisError::WrongType { command, key }) => assert_eq!(("LPUSH", "name"), (command.as_str(), key.as_str())),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        match client.llen("name") {
+            Err(e @ RedisError::WrongType { .. }) => assert!(e.to_string().contains("name")),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(client.lpush("queue", &[]).is_err());
+    }
+
+    #[test]
+    fn pushx_only_onto_existing_list() {
+        let server = MockServer::new(&[":0\r\n", "*0\r\n", ":3\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(0, client.lpushx("missing", &["a"]).unwrap());
+        assert!(client.lrange("missing", 0, -1).unwrap().is_empty());
+        assert_eq!(3, client.rpushx("queue", &["b", "c"]).unwrap());
+        assert!(client.rpushx("queue", &[]).is_err());
+        drop(client);
+        assert_eq!(
+            vec![vec!["LPUSHX", "missing", "a"], vec!["LRANGE", "missing", "0", "-1"], vec!["RPUSHX", "queue", "b", "c"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn lrange_in_chunks_with_short_tail() {
+        let server = MockServer::new(&[
+            ":5\r\n",
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n",
+            "*2\r\n$1\r\nc\r\n$1\r\nd\r\n",
+            "*0\r\n",
+        ]);
+        let mut client = server.client();
+        let mut chunks = Vec::new();
+
+        client.lrange_chunked("list", 2, |chunk| chunks.push(chunk.to_vec())).unwrap();
+        assert_eq!(vec![vec!["a", "b"], vec!["c", "d"]], chunks);
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["LLEN", "list"],
+                vec!["LRANGE", "list", "0", "1"],
+                vec!["LRANGE", "list", "2", "3"],
+                vec!["LRANGE", "list", "4", "5"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn lrange_large_list_in_chunks() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        Cmd::cmd("DEL").arg("test_chunked_list").execute(&mut client).unwrap();
+        for i in 0..500 {
+            Cmd::cmd("RPUSH").arg("test_chunked_list").arg(&i.to_string()).execute(&mut client).unwrap();
+        }
+
+        let mut sizes = Vec::new();
+        client.lrange_chunked("test_chunked_list", 100, |chunk| sizes.push(chunk.len())).unwrap();
+        assert_eq!(vec![100; 5], sizes);
+    }
+
+    #[test]
+    fn llen_of_list_and_missing_key() {
+        let server = MockServer::new(&[":3\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(3, client.llen("list").unwrap());
+        assert_eq!(0, client.llen("missing").unwrap());
+    }
+
+    #[test]
+    fn llen_of_wrong_type() {
+        let server = MockServer::new(&["-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"]);
+        let mut client = server.client();
+
+        assert!(matches!(client.llen("string"), Err(RedisError::WrongType { key, .. }) if key == "string"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::{FromRedisValue, HashFields, RedisError, Values};
+
+mod args;
+mod connection;
+mod hash;
+#[cfg(feature = "serde")]
+mod json;
+mod keys;
+mod lists;
+mod scripting;
+mod server;
+mod sets;
+mod streams;
+mod strings;
+mod zsets;
+
+pub(crate) use connection::Credentials;
+pub(crate) use keys::{is_read_only, EncodingWatch, TypeCache};
+pub use keys::{Encoding, ExpireOptions, RedisType};
+pub use scripting::RateLimitResult;
+pub use server::{Capabilities, ClientKillFilters, ClientType, FailoverOptions, LatencyStats, ShutdownMode, Timings};
+pub use streams::TrimStrategy;
+pub use strings::{BitOp, BitUnit, GetExExpiry, SetOptions};
+pub use zsets::{to_redis_score_arg, Aggregate, ZAddOptions};
+
+///Turn error reply into server error
+fn check(value: Values) -> Result<Values, RedisError> {
+    match value {
+        Values::Errors(e) => Err(RedisError::server(e)),
+        value => Ok(value),
+    }
+}
+
+///Check reply of write command is `+OK`
+fn expect_ok(value: Values) -> Result<(), RedisError> {
+    match check(value)? {
+        value if value.is_ok() => Ok(()),
+        other => Err(RedisError::unexpected_reply("OK", &other)),
+    }
+}
+
+fn into_optional_string(value: Values) -> Result<Option<String>, RedisError> {
+    match check(value)? {
+        Values::BulkString(s) | Values::SimpleString(s) => Ok(Some(s)),
+        Values::Nil => Ok(None),
+        Values::BulkBytes(bytes) => Err(RedisError::Protocol(format!("Reply isn't valid UTF-8: {:?}", bytes))),
+        other => Err(RedisError::unexpected_reply("string", &other)),
+    }
+}
+
+fn into_optional_bytes(value: Values) -> Result<Option<Vec<u8>>, RedisError> {
+    match check(value)? {
+        Values::BulkBytes(bytes) => Ok(Some(bytes)),
+        Values::BulkString(s) | Values::SimpleString(s) => Ok(Some(s.into_bytes())),
+        Values::Nil => Ok(None),
+        other => Err(RedisError::unexpected_reply("string", &other)),
+    }
+}
+
+fn into_array(value: Values) -> Result<Vec<Values>, RedisError> {
+    match check(value)? {
+        Values::Arrays(values) => Ok(values),
+        other => Err(RedisError::unexpected_reply("array", &other)),
+    }
+}
+
+fn into_integer(value: Values) -> Result<i64, RedisError> {
+    match check(value)? {
+        Values::Integers(i) => Ok(i),
+        other => Err(RedisError::unexpected_reply("integer", &other)),
+    }
+}
+
+fn into_strings(value: Values) -> Result<Vec<String>, RedisError> {
+    Vec::<String>::from_redis_value(check(value)?)
+}
+
+///Convert flat array of field value pairs or map reply keeping order of server
+fn into_string_pairs(value: Values) -> Result<Vec<(String, String)>, RedisError> {
+    let pairs = match check(value)? {
+        Values::Arrays(values) => {
+            if values.len() % 2 != 0 {
+                return Err(RedisError::Protocol(format!("Odd count {} of field value elements", values.len())));
+            }
+
+            let mut values = values.into_iter();
+            let mut pairs = Vec::with_capacity(values.len() / 2);
+            while let (Some(field), Some(value)) = (values.next(), values.next()) {
+                pairs.push((field, value));
+            }
+
+            pairs
+        },
+        Values::Map(pairs) => pairs,
+        other => return Err(RedisError::unexpected_reply("array", &other)),
+    };
+
+    pairs.into_iter()
+        .map(|(field, value)| Ok((String::from_redis_value(field)?, String::from_redis_value(value)?)))
+        .collect()
+}
+
+///Convert flat array of field value pairs or map reply
+fn into_string_map(value: Values) -> Result<HashMap<String, String>, RedisError> {
+    let fields = match check(value)? {
+        value @ Values::Arrays(_) | value @ Values::Map(_) => HashFields::from_redis_value(value)?,
+        other => return Err(RedisError::unexpected_reply("array", &other)),
+    };
+
+    fields.into_iter()
+        .map(|(field, value)| Ok((field, String::from_redis_value(value)?)))
+        .collect()
+}
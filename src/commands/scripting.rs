@@ -0,0 +1,133 @@
+use std::{convert::TryFrom, time::Duration};
+
+use crate::{Client, Cmd, RedisError, Values};
+
+use super::{into_array, into_integer};
+
+///Count request in window, window starts with first request
+const RATE_LIMIT_SCRIPT: &str = "\
+local count = redis.call('INCR', KEYS[1])
+local ttl = redis.call('PTTL', KEYS[1])
+if ttl < 0 then
+    redis.call('PEXPIRE', KEYS[1], ARGV[1])
+    ttl = tonumber(ARGV[1])
+end
+return {count, ttl}";
+
+///Decision of `rate_limit`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RateLimitResult {
+    ///Request fits into limit of window
+    pub allowed: bool,
+    ///Count of requests still allowed in window
+    pub remaining: u64,
+    ///Time until window ends and counter starts again
+    pub reset_after: Duration,
+}
+
+impl Client {
+    ///Run Lua script on server, `keys` are passed as `KEYS` and `args` as `ARGV`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let value = client.eval("return redis.call('GET', KEYS[1])", &["key"], &[]).unwrap();
+    ///```
+    pub fn eval(&mut self, script: &str, keys: &[&str], args: &[&str]) -> Result<Values, RedisError> {
+        let cmd = Cmd::cmd("EVAL").arg(script).arg(&keys.len().to_string());
+        let cmd = keys.iter().fold(cmd, |cmd, key| cmd.key(key));
+
+        cmd.args(args).run(self)
+    }
+
+    ///Count request in fixed window of key, at most `limit` requests are allowed until window ends
+    ///
+    ///Counter and its expiry are set by one script, so concurrent clients share limit.
+    ///Window starts with first request counted by key
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let result = client.rate_limit("limit:user:1", 100, Duration::from_secs(60)).unwrap();
+    ///if !result.allowed {
+    ///    println!("retry after {:?}", result.reset_after);
+    ///}
+    ///```
+    pub fn rate_limit(&mut self, key: &str, limit: u64, window: Duration) -> Result<RateLimitResult, RedisError> {
+        let window_ms = window.as_millis();
+        if window_ms == 0 {
+            return Err(RedisError::InvalidArgument(String::from("Rate limit window must be at least 1 millisecond")));
+        }
+
+        let reply = into_array(self.eval(RATE_LIMIT_SCRIPT, &[key], &[&window_ms.to_string()])?)?;
+        let (count, ttl) = match <[Values; 2]>::try_from(reply) {
+            Ok([count, ttl]) => (into_integer(count)?, into_integer(ttl)?),
+            Err(reply) => {
+                return Err(RedisError::Protocol(format!("Rate limit script returned {} values", reply.len())));
+            },
+        };
+        let count = count.max(0) as u64;
+
+        Ok(RateLimitResult {
+            allowed: count <= limit,
+            remaining: limit.saturating_sub(count),
+            reset_after: Duration::from_millis(ttl.max(0) as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RATE_LIMIT_SCRIPT;
+    use crate::mock::MockServer;
+    use crate::{Client, RateLimitResult, RedisError};
+
+    #[test]
+    fn rate_limit_denies_after_limit() {
+        let server = MockServer::new(&["*2\r\n:1\r\n:60000\r\n", "*2\r\n:2\r\n:59000\r\n", "*2\r\n:3\r\n:58000\r\n"]);
+        let mut client = server.client();
+        let window = Duration::from_secs(60);
+
+        assert_eq!(
+            RateLimitResult { allowed: true, remaining: 1, reset_after: window },
+            client.rate_limit("limit", 2, window).unwrap()
+        );
+        assert_eq!(
+            RateLimitResult { allowed: true, remaining: 0, reset_after: Duration::from_secs(59) },
+            client.rate_limit("limit", 2, window).unwrap()
+        );
+        assert_eq!(
+            RateLimitResult { allowed: false, remaining: 0, reset_after: Duration::from_secs(58) },
+            client.rate_limit("limit", 2, window).unwrap()
+        );
+        drop(client);
+        assert_eq!(vec!["EVAL", RATE_LIMIT_SCRIPT, "1", "limit", "60000"], server.commands()[0]);
+    }
+
+    #[test]
+    fn rate_limit_rejects_empty_window() {
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+
+        assert!(matches!(client.rate_limit("limit", 2, Duration::from_micros(10)), Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn rate_limit_window_on_server() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        crate::Cmd::cmd("DEL").arg("test_rate_limit").execute(&mut client).unwrap();
+
+        for remaining in (0..3).rev() {
+            let result = client.rate_limit("test_rate_limit", 3, Duration::from_secs(10)).unwrap();
+            assert!(result.allowed);
+            assert_eq!(remaining, result.remaining);
+        }
+        let denied = client.rate_limit("test_rate_limit", 3, Duration::from_secs(10)).unwrap();
+        assert!(!denied.allowed);
+        assert!(denied.reset_after <= Duration::from_secs(10));
+    }
+}
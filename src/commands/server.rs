@@ -0,0 +1,926 @@
+use std::{collections::HashMap, io::ErrorKind, time::Duration};
+
+use crate::{is_disconnect, Client, Cmd, FromRedisValue, HashFields, RedisError, Values};
+
+use super::args::{ArgWriter, FlagSpec};
+use super::{check, expect_ok, into_integer, into_optional_string, into_string_map, into_strings};
+
+///How long `is_ready` waits for `PONG`
+const READY_TIMEOUT: Duration = Duration::from_secs(1);
+///Delay between `PING`s of `wait_until_ready` while server is loading
+const LOADING_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static FAILOVER_FLAGS: FlagSpec = FlagSpec {
+    order: &["TO", "FORCE", "ABORT", "TIMEOUT"],
+    exclusive: &[&["ABORT", "TO"], &["ABORT", "FORCE"]],
+};
+
+///Arguments of `FAILOVER` command
+///```
+///use std::time::Duration;
+///use redis_client::FailoverOptions;
+///
+///let options = FailoverOptions::new().to("10.0.0.2", 6379).force().timeout(Duration::from_secs(5));
+///let cancel = FailoverOptions::new().abort();
+///```
+#[derive(Debug, Default, Clone)]
+pub struct FailoverOptions {
+    to: Option<(String, u16)>,
+    force: bool,
+    abort: bool,
+    timeout: Option<Duration>,
+}
+
+impl FailoverOptions {
+    pub fn new() -> Self {
+        FailoverOptions::default()
+    }
+
+    ///Fail over to given replica instead of any of them
+    pub fn to(mut self, host: &str, port: u16) -> Self {
+        self.to = Some((host.to_string(), port));
+
+        self
+    }
+
+    ///Fail over even if replica doesn't catch up before timeout, requires `to`
+    pub fn force(mut self) -> Self {
+        self.force = true;
+
+        self
+    }
+
+    ///Cancel failover in progress
+    pub fn abort(mut self) -> Self {
+        self.abort = true;
+
+        self
+    }
+
+    ///Time to wait for replica to catch up
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    fn write(&self, cmd: Cmd) -> Result<Cmd, RedisError> {
+        if self.force && self.to.is_none() && !self.abort {
+            return Err(RedisError::InvalidArgument(String::from("Flag FORCE requires TO")));
+        }
+
+        ArgWriter::new(&FAILOVER_FLAGS)
+            .flag_values("TO", self.to.as_ref().map(|(host, port)| vec![host.clone(), port.to_string()]))
+            .flag("FORCE", self.force)
+            .flag("ABORT", self.abort)
+            .flag_value("TIMEOUT", self.timeout.map(|timeout| timeout.as_millis()))
+            .write(cmd)
+    }
+}
+
+static CLIENT_KILL_FLAGS: FlagSpec = FlagSpec {
+    order: &["ADDR", "LADDR", "TYPE", "SKIPME"],
+    exclusive: &[],
+};
+
+///Kind of connection matched by `TYPE` filter of `CLIENT KILL`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ClientType {
+    Normal,
+    Master,
+    Replica,
+    PubSub,
+}
+
+impl ClientType {
+    fn as_arg(self) -> &'static str {
+        match self {
+            ClientType::Normal => "normal",
+            ClientType::Master => "master",
+            ClientType::Replica => "replica",
+            ClientType::PubSub => "pubsub",
+        }
+    }
+}
+
+///Filters of `CLIENT KILL`, connection is killed if it matches all of them
+///```
+///use redis_client::{ClientKillFilters, ClientType};
+///
+///let filters = ClientKillFilters::new().client_type(ClientType::PubSub).skip_me(true);
+///```
+#[derive(Debug, Default, Clone)]
+pub struct ClientKillFilters {
+    addr: Option<String>,
+    laddr: Option<String>,
+    client_type: Option<ClientType>,
+    skip_me: Option<bool>,
+}
+
+impl ClientKillFilters {
+    pub fn new() -> Self {
+        ClientKillFilters::default()
+    }
+
+    ///Remote address of client as `ip:port`
+    pub fn addr(mut self, addr: &str) -> Self {
+        self.addr = Some(addr.to_string());
+
+        self
+    }
+
+    ///Local address of server socket client is connected to as `ip:port`
+    pub fn laddr(mut self, laddr: &str) -> Self {
+        self.laddr = Some(laddr.to_string());
+
+        self
+    }
+
+    ///Kind of connection
+    pub fn client_type(mut self, client_type: ClientType) -> Self {
+        self.client_type = Some(client_type);
+
+        self
+    }
+
+    ///Whether to spare connection sending command, server spares it by default
+    pub fn skip_me(mut self, skip: bool) -> Self {
+        self.skip_me = Some(skip);
+
+        self
+    }
+
+    fn write(&self, cmd: Cmd) -> Result<Cmd, RedisError> {
+        if self.addr.is_none() && self.laddr.is_none() && self.client_type.is_none() && self.skip_me.is_none() {
+            return Err(RedisError::InvalidArgument(String::from("CLIENT KILL requires at least one filter")));
+        }
+
+        ArgWriter::new(&CLIENT_KILL_FLAGS)
+            .flag_value("ADDR", self.addr.as_ref())
+            .flag_value("LADDR", self.laddr.as_ref())
+            .flag_value("TYPE", self.client_type.map(ClientType::as_arg))
+            .flag_value("SKIPME", self.skip_me.map(|skip| if skip { "yes" } else { "no" }))
+            .write(cmd)
+    }
+}
+
+///Whether `SHUTDOWN` saves dataset before exit
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ShutdownMode {
+    ///Save even if no save points are configured
+    Save,
+    ///Exit without saving
+    NoSave,
+    ///Save only if save points are configured
+    Default,
+}
+
+///Round trip times of several `PING`s
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LatencyStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub avg: Duration,
+}
+
+///Phases of one command round trip, both measured from start of write
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Timings {
+    ///Until first byte of reply arrived, mostly network latency and server processing
+    pub first_byte: Duration,
+    ///Until whole reply was read, difference to `first_byte` is transfer of payload
+    pub total: Duration,
+}
+
+///Server features found by `warm_up`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Capabilities {
+    ///Protocol version of connection, `2` if server doesn't know `HELLO`
+    pub protocol: i64,
+    ///Value of `redis_version` field of `INFO server`
+    pub server_version: String,
+    ///Count of commands known by server
+    pub command_count: i64,
+}
+
+impl Client {
+    ///Query protocol, server version and count of commands once and cache them
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let capabilities = client.warm_up().unwrap();
+    ///println!("redis {} over RESP{}", capabilities.server_version, capabilities.protocol);
+    ///```
+    pub fn warm_up(&mut self) -> Result<Capabilities, RedisError> {
+        let protocol = match Cmd::cmd("HELLO").run(self) {
+            Ok(hello) => HashFields::from_redis_value(hello)?.take("proto")?,
+            Err(RedisError::Server { ref code, ref message }) if code == "ERR" && message.starts_with("unknown command") => 2,
+            Err(e) => return Err(e),
+        };
+
+        let info = String::from_redis_value(Cmd::cmd("INFO").arg("server").run(self)?)?;
+        let server_version = info.lines()
+            .find_map(|line| line.strip_prefix("redis_version:"))
+            .ok_or_else(|| RedisError::MissingField(String::from("redis_version")))?
+            .trim()
+            .to_string();
+
+        let command_count = into_integer(Cmd::cmd("COMMAND").arg("COUNT").run(self)?)?;
+
+        let capabilities = Capabilities { protocol, server_version, command_count };
+        self.capabilities = Some(capabilities.clone());
+
+        Ok(capabilities)
+    }
+
+    ///Capabilities cached by last `warm_up`
+    pub fn capabilities(&self) -> Option<&Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    ///Names of all commands server supports sorted alphabetically, issues `COMMAND LIST`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let commands = client.command_list().unwrap();
+    ///let has_getex = commands.iter().any(|command| command == "getex");
+    ///```
+    pub fn command_list(&mut self) -> Result<Vec<String>, RedisError> {
+        let mut names = into_strings(Cmd::cmd("COMMAND").arg("LIST").run(self)?)?;
+        names.sort();
+
+        Ok(names)
+    }
+
+    ///Execute command measuring time to first byte and to whole reply
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let (value, timings) = client.execute_timed(Cmd::cmd("GET").arg("big_value")).unwrap();
+    ///println!("first byte {:?}, transfer {:?}", timings.first_byte, timings.total - timings.first_byte);
+    ///```
+    pub fn execute_timed(&mut self, cmd: Cmd) -> Result<(Values, Timings), RedisError> {
+        let start = self.clock.now();
+        self.first_byte_at = Some(start);
+        let value = cmd.execute(self);
+        let first_byte = self.first_byte_at.take().map_or(Duration::from_secs(0), |at| at.duration_since(start));
+        let value = value?;
+
+        Ok((value, Timings { first_byte, total: self.clock.now().duration_since(start) }))
+    }
+
+    ///Check connection by `PING`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.ping().unwrap();
+    ///```
+    pub fn ping(&mut self) -> Result<(), RedisError> {
+        match check(Cmd::cmd("PING").run(self)?)? {
+            Values::SimpleString(ref s) if s == "PONG" => Ok(()),
+            other => Err(RedisError::unexpected_reply("PONG", &other)),
+        }
+    }
+
+    ///Measure round trip of one `PING`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let latency = client.ping_latency().unwrap();
+    ///```
+    pub fn ping_latency(&mut self) -> Result<Duration, RedisError> {
+        let start = self.clock.now();
+        self.ping()?;
+
+        Ok(self.clock.now().duration_since(start))
+    }
+
+    ///Measure round trips of `n` sequential `PING`s
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let stats = client.latency_samples(10).unwrap();
+    ///println!("min {:?} max {:?} avg {:?}", stats.min, stats.max, stats.avg);
+    ///```
+    pub fn latency_samples(&mut self, n: usize) -> Result<LatencyStats, RedisError> {
+        if n == 0 {
+            return Err(RedisError::InvalidArgument(String::from("At least one sample is required")));
+        }
+
+        let mut samples = Vec::with_capacity(n);
+        for _ in 0..n {
+            samples.push(self.ping_latency()?);
+        }
+
+        Ok(LatencyStats {
+            min: samples.iter().copied().min().unwrap_or_default(),
+            max: samples.iter().copied().max().unwrap_or_default(),
+            avg: samples.iter().sum::<Duration>() / n as u32,
+        })
+    }
+
+    ///Server answers `PING`, server loading dataset is alive too
+    pub fn is_alive(&mut self) -> bool {
+        match self.ping() {
+            Ok(()) => true,
+            Err(e) => e.is_loading(),
+        }
+    }
+
+    ///Server answers `PING` in time and isn't loading dataset, intended for load balancer health checks
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///if !client.is_ready() {
+    ///    println!("redis is not ready");
+    ///}
+    ///```
+    pub fn is_ready(&mut self) -> bool {
+        self.ready_within(READY_TIMEOUT)
+    }
+
+    ///Probe of `is_ready`, connection is replaced when `PONG` doesn't arrive in time so late one isn't
+    ///read as reply of next command
+    fn ready_within(&mut self, timeout: Duration) -> bool {
+        let stream = self.connect.get_ref();
+        let previous = match stream.read_timeout() {
+            Ok(timeout) => timeout,
+            Err(_) => return false,
+        };
+        if stream.set_read_timeout(Some(timeout)).is_err() {
+            return false;
+        }
+
+        let reply = self.ping();
+        let _ = self.connect.get_ref().set_read_timeout(previous);
+
+        match reply {
+            Ok(()) => true,
+            Err(ref e) if matches!(e.io_error(), Some(io) if !is_disconnect(io)) => {
+                self.abandon_reply();
+
+                false
+            },
+            Err(_) => false,
+        }
+    }
+
+    ///Wait until server answers `PING` after loading dataset, fails with `RedisError::TimedOut` if it's still loading after `timeout`
+    ///
+    ///Errors other than `-LOADING` are returned immediately
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.wait_until_ready(Duration::from_secs(30)).unwrap();
+    ///```
+    pub fn wait_until_ready(&mut self, timeout: Duration) -> Result<(), RedisError> {
+        let deadline = self.clock.now() + timeout;
+
+        loop {
+            match self.ping() {
+                Err(e) if e.is_loading() => {
+                    let left = deadline.checked_duration_since(self.clock.now()).unwrap_or_default();
+                    if left == Duration::from_secs(0) {
+                        return Err(RedisError::TimedOut);
+                    }
+
+                    self.clock.sleep(left.min(LOADING_POLL_INTERVAL));
+                },
+                result => return result,
+            }
+        }
+    }
+
+    ///Get configuration parameters matching pattern
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let config = client.config_get("max*").unwrap();
+    ///```
+    pub fn config_get(&mut self, pattern: &str) -> Result<HashMap<String, String>, RedisError> {
+        into_string_map(Cmd::cmd("CONFIG").arg("GET").arg(pattern).expect_resp3().run(self)?)
+    }
+
+    ///Start coordinated failover from this master to one of its replicas
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::{Client, FailoverOptions};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.failover(FailoverOptions::new().timeout(Duration::from_secs(5))).unwrap();
+    ///```
+    pub fn failover(&mut self, opts: FailoverOptions) -> Result<(), RedisError> {
+        let cmd = opts.write(Cmd::cmd("FAILOVER"))?;
+
+        expect_ok(cmd.run(self)?)
+    }
+
+    ///Kill connection by its id, returns whether connection was found
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let killed = client.client_kill_id(42).unwrap();
+    ///```
+    pub fn client_kill_id(&mut self, id: i64) -> Result<bool, RedisError> {
+        Ok(into_integer(Cmd::cmd("CLIENT").arg("KILL").arg("ID").arg(&id.to_string()).run(self)?)? > 0)
+    }
+
+    ///Kill connections matching all filters, returns count of killed connections
+    ///```no_run
+    ///use redis_client::{Client, ClientKillFilters, ClientType};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let killed = client.client_kill(ClientKillFilters::new().client_type(ClientType::Normal)).unwrap();
+    ///```
+    pub fn client_kill(&mut self, filters: ClientKillFilters) -> Result<i64, RedisError> {
+        let cmd = filters.write(Cmd::cmd("CLIENT").arg("KILL"))?;
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Start saving dataset in background, status is accepted also if save is scheduled after running rewrite
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.bgsave().unwrap();
+    ///```
+    pub fn bgsave(&mut self) -> Result<(), RedisError> {
+        expect_background(Cmd::cmd("BGSAVE").run(self)?)
+    }
+
+    ///Start rewriting append only file in background
+    pub fn bgrewriteaof(&mut self) -> Result<(), RedisError> {
+        expect_background(Cmd::cmd("BGREWRITEAOF").run(self)?)
+    }
+
+    ///Get Unix timestamp of last successful save
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let saved_at = client.lastsave().unwrap();
+    ///```
+    pub fn lastsave(&mut self) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("LASTSAVE").run(self)?)
+    }
+
+    ///Stop server, connection is closed by server on success
+    ///```no_run
+    ///use redis_client::{Client, ShutdownMode};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.shutdown(ShutdownMode::NoSave).unwrap();
+    ///```
+    pub fn shutdown(&mut self, save: ShutdownMode) -> Result<(), RedisError> {
+        let cmd = match save {
+            ShutdownMode::Save => Cmd::cmd("SHUTDOWN").arg("SAVE"),
+            ShutdownMode::NoSave => Cmd::cmd("SHUTDOWN").arg("NOSAVE"),
+            ShutdownMode::Default => Cmd::cmd("SHUTDOWN"),
+        };
+
+        //Closed connection is expected reply, it must not be reconnected and sent again
+        cmd.validate(self)?;
+        let bytes = cmd.encode(self);
+        match self.execute_once(&bytes) {
+            Err(RedisError::Io(e)) if is_closed(&e) => Ok(()),
+            Ok(Values::Errors(e)) => Err(RedisError::server(e)),
+            Ok(other) => Err(RedisError::unexpected_reply("closed connection", &other)),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///Validate argument count of commands before sending, arities are loaded by `COMMAND INFO` on first use of command
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.enable_arity_check();
+    ///assert!(Cmd::cmd("SET").arg("key").execute(&mut client).is_err());
+    ///```
+    pub fn enable_arity_check(&mut self) {
+        if self.arities.is_none() {
+            self.arities = Some(HashMap::new());
+        }
+    }
+
+    ///Reject command with wrong argument count, negative arity is minimal count
+    pub(crate) fn check_arity(&mut self, args: &[Vec<u8>]) -> Result<(), RedisError> {
+        let name = String::from_utf8_lossy(&args[0]).to_lowercase();
+        let arity = match self.arities.as_ref().map(|arities| arities.get(&name).copied()) {
+            None => return Ok(()),
+            Some(Some(arity)) => arity,
+            Some(None) => {
+                let arity = self.command_arity(&name)?;
+                if let Some(arities) = self.arities.as_mut() {
+                    arities.insert(name.clone(), arity);
+                }
+
+                arity
+            },
+        };
+
+        let count = args.len() as i64;
+        match arity {
+            Some(arity) if arity >= 0 && count != arity => Err(RedisError::InvalidArgument(format!(
+                "Command {} takes {} arguments, got {}", name, arity - 1, count - 1
+            ))),
+            Some(arity) if arity < 0 && count < -arity => Err(RedisError::InvalidArgument(format!(
+                "Command {} takes at least {} arguments, got {}", name, -arity - 1, count - 1
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    ///Load arity of command, `None` if server doesn't know it
+    fn command_arity(&mut self, name: &str) -> Result<Option<i64>, RedisError> {
+        let reply = self.execute(Cmd::create_command(&Cmd::cmd("COMMAND").arg("INFO").arg(name).args), true)?;
+
+        match check(reply)? {
+            Values::Arrays(mut infos) if infos.len() == 1 => match infos.remove(0) {
+                Values::Arrays(info) => match info.get(1) {
+                    Some(Values::Integers(arity)) => Ok(Some(*arity)),
+                    Some(other) => Err(RedisError::unexpected_reply("integer", other)),
+                    None => Err(RedisError::Protocol(String::from("COMMAND INFO reply is too short"))),
+                },
+                Values::Nil => Ok(None),
+                other => Err(RedisError::unexpected_reply("array", &other)),
+            },
+            other => Err(RedisError::unexpected_reply("array", &other)),
+        }
+    }
+
+    ///Issue any `DEBUG` subcommand and return parsed reply
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let reply = client.debug("QUICKLIST-PACKED-THRESHOLD", &["1K"]);
+    ///```
+    pub fn debug(&mut self, subcommand: &str, args: &[&str]) -> Result<Values, RedisError> {
+        check(Cmd::cmd("DEBUG").arg(subcommand).args(args).run(self)?)
+    }
+
+    ///Get debugging information about key
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let info = client.debug_object("key");
+    ///```
+    pub fn debug_object(&mut self, key: &str) -> Result<String, RedisError> {
+        into_optional_string(check(Cmd::cmd("DEBUG").arg("OBJECT").key(key).run(self)?)?)?
+            .ok_or_else(|| RedisError::Protocol(String::from("Empty DEBUG OBJECT reply")))
+    }
+
+    ///Block server for given time
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.debug_sleep(Duration::from_millis(100)).unwrap();
+    ///```
+    pub fn debug_sleep(&mut self, duration: Duration) -> Result<(), RedisError> {
+        expect_ok(self.debug("SLEEP", &[&duration.as_secs_f64().to_string()])?)
+    }
+}
+
+///Check status of command starting background job, like `Background saving started`
+fn expect_background(value: Values) -> Result<(), RedisError> {
+    match check(value)? {
+        Values::SimpleString(ref s) if s.starts_with("Background") => Ok(()),
+        other => Err(RedisError::unexpected_reply("background job status", &other)),
+    }
+}
+
+///Connection was closed by server after command
+fn is_closed(e: &std::io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::UnexpectedEof | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::mock::MockServer;
+    use crate::{Capabilities, ClientKillFilters, ClientType, Cmd, FailoverOptions, RedisError, ShutdownMode, Timings, Values};
+
+    #[test]
+    fn config_get_from_flat_array() {
+        let server = MockServer::new(&["*4\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n$7\r\nmaxconn\r\n$2\r\n10\r\n"]);
+        let mut client = server.client();
+
+        let config = client.config_get("max*").unwrap();
+        assert_eq!(Some(&String::from("0")), config.get("maxmemory"));
+        assert_eq!(Some(&String::from("10")), config.get("maxconn"));
+    }
+
+    #[test]
+    fn debug_passthrough() {
+        let server = MockServer::new(&["+OK\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Values::SimpleString(String::from("OK")), client.debug("JMAP", &[]).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["DEBUG", "JMAP"]], server.commands());
+    }
+
+    #[test]
+    fn failover_to_replica() {
+        let server = MockServer::new(&["+OK\r\n", "+OK\r\n"]);
+        let mut client = server.client();
+
+        client.failover(FailoverOptions::new().timeout(Duration::from_millis(1500)).force().to("10.0.0.2", 6380)).unwrap();
+        client.failover(FailoverOptions::new().abort()).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["FAILOVER", "TO", "10.0.0.2", "6380", "FORCE", "TIMEOUT", "1500"],
+                vec!["FAILOVER", "ABORT"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn failover_abort_not_combined() {
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+
+        let to = client.failover(FailoverOptions::new().abort().to("10.0.0.2", 6380));
+        let force = client.failover(FailoverOptions::new().abort().force());
+        let force_alone = client.failover(FailoverOptions::new().force());
+
+        assert!(matches!(to, Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(force, Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(force_alone, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn warm_up_caches_capabilities() {
+        let server = MockServer::new(&[
+            "*14\r\n$6\r\nserver\r\n$5\r\nredis\r\n$7\r\nversion\r\n$5\r\n7.2.4\r\n$5\r\nproto\r\n:2\r\n\
+            $2\r\nid\r\n:5\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n$4\r\nrole\r\n$6\r\nmaster\r\n$7\r\nmodules\r\n*0\r\n",
+            "$54\r\n# Server\r\nredis_version:7.2.4\r\nredis_mode:standalone\r\n\r\n",
+            ":240\r\n",
+        ]);
+        let mut client = server.client();
+
+        assert_eq!(None, client.capabilities());
+        let expected = Capabilities { protocol: 2, server_version: String::from("7.2.4"), command_count: 240 };
+        assert_eq!(expected, client.warm_up().unwrap());
+        assert_eq!(Some(&expected), client.capabilities());
+        drop(client);
+        assert_eq!(vec![vec!["HELLO"], vec!["INFO", "server"], vec!["COMMAND", "COUNT"]], server.commands());
+    }
+
+    #[test]
+    fn command_list_sorted() {
+        let server = MockServer::new(&["*3\r\n$3\r\nset\r\n$5\r\nhmget\r\n$3\r\nget\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(vec!["get", "hmget", "set"], client.command_list().unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["COMMAND", "LIST"]], server.commands());
+    }
+
+    #[test]
+    fn client_kill_by_filters_and_id() {
+        let server = MockServer::new(&[":2\r\n", ":1\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        let filters = ClientKillFilters::new().skip_me(false).client_type(ClientType::PubSub).laddr("127.0.0.1:6379");
+        assert_eq!(2, client.client_kill(filters).unwrap());
+        assert!(client.client_kill(ClientKillFilters::new()).is_err());
+        assert!(client.client_kill_id(42).unwrap());
+        assert!(!client.client_kill_id(43).unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["CLIENT", "KILL", "LADDR", "127.0.0.1:6379", "TYPE", "pubsub", "SKIPME", "no"],
+                vec!["CLIENT", "KILL", "ID", "42"],
+                vec!["CLIENT", "KILL", "ID", "43"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn persistence_commands() {
+        let server = MockServer::new(&[
+            "+Background saving started\r\n",
+            "+Background append only file rewriting started\r\n",
+            ":1700000000\r\n",
+            "+OK\r\n",
+        ]);
+        let mut client = server.client();
+
+        client.bgsave().unwrap();
+        client.bgrewriteaof().unwrap();
+        assert_eq!(1_700_000_000, client.lastsave().unwrap());
+        assert!(matches!(client.bgsave(), Err(RedisError::UnexpectedReply { .. })));
+        drop(client);
+        assert_eq!(vec![vec!["BGSAVE"], vec!["BGREWRITEAOF"], vec!["LASTSAVE"], vec!["BGSAVE"]], server.commands());
+    }
+
+    #[test]
+    fn shutdown_closes_connection() {
+        let server = MockServer::new(&[""]);
+        let mut client = server.client();
+
+        client.shutdown(ShutdownMode::NoSave).unwrap();
+        drop(client);
+        assert_eq!(vec![vec!["SHUTDOWN", "NOSAVE"]], server.commands());
+    }
+
+    #[test]
+    fn shutdown_not_retried_by_auto_reconnect() {
+        use std::{io::{BufReader, ErrorKind}, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = crate::Client::new(listener.local_addr().unwrap()).unwrap();
+        client.set_auto_reconnect(true);
+        let (stream, _) = listener.accept().unwrap();
+        let server = std::thread::spawn(move || {
+            let mut reader = BufReader::new(stream);
+            crate::parse_response(&mut reader).unwrap()
+        });
+
+        client.shutdown(ShutdownMode::NoSave).unwrap();
+        assert_eq!(
+            Values::Arrays(vec![Values::BulkString(String::from("SHUTDOWN")), Values::BulkString(String::from("NOSAVE"))]),
+            server.join().unwrap()
+        );
+        listener.set_nonblocking(true).unwrap();
+        assert_eq!(ErrorKind::WouldBlock, listener.accept().unwrap_err().kind());
+    }
+
+    #[test]
+    fn shutdown_error_reply() {
+        let server = MockServer::new(&["-ERR Errors trying to SHUTDOWN. Check logs.\r\n"]);
+        let mut client = server.client();
+
+        match client.shutdown(ShutdownMode::Save) {
+            Err(RedisError::Server { code, message }) => {
+                assert_eq!("ERR", code);
+                assert!(message.contains("SHUTDOWN"));
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arity_checked_after_command_info() {
+        let server = MockServer::new(&["*1\r\n*3\r\n$3\r\nset\r\n:-3\r\n*0\r\n", "+OK\r\n"]);
+        let mut client = server.client();
+        client.enable_arity_check();
+
+        match Cmd::cmd("SET").arg("key").execute(&mut client) {
+            Err(RedisError::InvalidArgument(message)) => assert_eq!("Command set takes at least 2 arguments, got 1", message),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        client.set("key", "value").unwrap();
+        drop(client);
+        assert_eq!(vec![vec!["COMMAND", "INFO", "set"], vec!["SET", "key", "value"]], server.commands());
+    }
+
+    #[test]
+    fn timed_execution() {
+        let server = MockServer::new(&["$5\r\nvalue\r\n"]);
+        let mut client = server.client();
+
+        let (value, timings) = client.execute_timed(Cmd::cmd("GET").arg("key")).unwrap();
+        assert_eq!(Values::BulkString(String::from("value")), value);
+        assert!(timings.first_byte > Duration::from_nanos(0));
+        assert!(timings.first_byte <= timings.total);
+    }
+
+    #[test]
+    fn timed_execution_resends_after_reconnect_and_uses_client_clock() {
+        let server = MockServer::sessions(&[&[], &["$5\r\nvalue\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+
+        let (value, timings) = client.execute_timed(Cmd::cmd("GET").arg("key")).unwrap();
+        assert_eq!(Values::BulkString(String::from("value")), value);
+        assert_eq!(Timings { first_byte: Duration::from_secs(0), total: Duration::from_secs(0) }, timings);
+    }
+
+    #[test]
+    fn ready_on_pong() {
+        let server = MockServer::new(&["+PONG\r\n"]);
+        let mut client = server.client();
+
+        assert!(client.is_ready());
+        drop(client);
+        assert_eq!(vec![vec!["PING"]], server.commands());
+    }
+
+    #[test]
+    fn wait_until_loaded() {
+        let loading = "-LOADING Redis is loading the dataset in memory\r\n";
+        let server = MockServer::new(&[loading, loading, "+PONG\r\n", loading]);
+        let mut client = server.client();
+
+        client.wait_until_ready(Duration::from_secs(1)).unwrap();
+        assert!(matches!(client.wait_until_ready(Duration::from_secs(0)), Err(RedisError::TimedOut)));
+        drop(client);
+        assert_eq!(vec![vec!["PING"]; 4], server.commands());
+    }
+
+    #[test]
+    fn late_pong_not_taken_by_next_command() {
+        let (addr, server) = crate::mock::slow_then_fast(Duration::from_millis(100), "+PONG\r\n", "$5\r\nvalue\r\n");
+        let mut client = crate::Client::new(addr).unwrap();
+
+        assert!(!client.ready_within(Duration::from_millis(20)));
+        assert_eq!(Some(String::from("value")), client.get("key").unwrap());
+        assert_eq!(vec![vec!["PING"], vec!["GET", "key"]], server.join().unwrap());
+    }
+
+    #[test]
+    fn wait_until_ready_polls_by_clock() {
+        let loading = "-LOADING Redis is loading the dataset in memory\r\n";
+        let server = MockServer::new(&[loading, loading, loading, loading]);
+        let mut client = server.client();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+
+        assert!(matches!(client.wait_until_ready(Duration::from_millis(120)), Err(RedisError::TimedOut)));
+        assert_eq!(
+            vec![Duration::from_millis(50), Duration::from_millis(50), Duration::from_millis(20)],
+            clock.sleeps()
+        );
+    }
+
+    #[test]
+    fn not_ready_while_loading() {
+        let loading = "-LOADING Redis is loading the dataset in memory\r\n";
+        let server = MockServer::new(&[loading, loading]);
+        let mut client = server.client();
+
+        assert!(!client.is_ready());
+        assert!(client.is_alive());
+    }
+
+    #[test]
+    fn ping_latency_is_measured() {
+        let server = MockServer::new(&["+PONG\r\n", "+PONG\r\n", "+PONG\r\n", "+PONG\r\n"]);
+        let mut client = server.client();
+
+        let latency = client.ping_latency().unwrap();
+        assert!(latency > Duration::from_nanos(0));
+        assert!(latency < Duration::from_secs(5));
+
+        let stats = client.latency_samples(3).unwrap();
+        assert!(stats.min <= stats.avg && stats.avg <= stats.max);
+        assert!(client.latency_samples(0).is_err());
+    }
+
+    #[test]
+    fn debug_object_prefixes_key() {
+        let server = MockServer::new(&["+Value at:0x7f refcount:1 encoding:int\r\n"]);
+        let mut client = server.client().with_prefix(String::from("app:"));
+
+        client.debug_object("counter").unwrap();
+        drop(client);
+        assert_eq!(vec![vec!["DEBUG", "OBJECT", "app:counter"]], server.commands());
+    }
+
+    #[test]
+    fn ping_latency_uses_client_clock() {
+        let server = MockServer::new(&["+PONG\r\n"]);
+        let mut client = server.client();
+        client.clock = crate::clock::MockClock::new();
+
+        assert_eq!(Duration::from_secs(0), client.ping_latency().unwrap());
+    }
+
+    #[test]
+    fn debug_object_and_sleep() {
+        let server = MockServer::new(&["+Value at:0x7f refcount:1 encoding:embstr\r\n", "+OK\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!("Value at:0x7f refcount:1 encoding:embstr", client.debug_object("key").unwrap());
+        client.debug_sleep(Duration::from_millis(500)).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![vec!["DEBUG", "OBJECT", "key"], vec!["DEBUG", "SLEEP", "0.5"]],
+            server.commands()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{Client, Cmd, RedisError};
+
+use super::{into_integer, into_strings};
+
+impl Client {
+    ///Get count of set members, missing key is empty set
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.scard("tags");
+    ///```
+    pub fn scard(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("SCARD").key(key).run(self)?)
+    }
+
+    ///Get random members of set
+    ///
+    ///Positive `count` gives distinct members, at most size of set. Negative `count` gives exactly `|count|` members
+    ///which may repeat, missing key gives no members
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let distinct = client.srandmember("tags", 3);
+    ///let with_repeats = client.srandmember("tags", -10);
+    ///```
+    pub fn srandmember(&mut self, key: &str, count: i64) -> Result<Vec<String>, RedisError> {
+        let members = into_strings(Cmd::cmd("SRANDMEMBER").key(key).arg(&count.to_string()).run(self)?)?;
+
+        let len = members.len() as i64;
+        let expected = if count < 0 { len == -count || len == 0 } else { len <= count };
+        if !expected {
+            return Err(RedisError::Protocol(format!("SRANDMEMBER with count {} returned {} members", count, len)));
+        }
+
+        Ok(members)
+    }
+
+    ///Store intersection of sets in `dest`, returns count of members in `dest`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.sinterstore("common", &["tags:1", "tags:2"]).unwrap();
+    ///```
+    pub fn sinterstore(&mut self, dest: &str, keys: &[&str]) -> Result<i64, RedisError> {
+        self.sstore("SINTERSTORE", dest, keys)
+    }
+
+    ///Store union of sets in `dest`, returns count of members in `dest`
+    pub fn sunionstore(&mut self, dest: &str, keys: &[&str]) -> Result<i64, RedisError> {
+        self.sstore("SUNIONSTORE", dest, keys)
+    }
+
+    ///Store members of first set missing in all others in `dest`, returns count of members in `dest`
+    pub fn sdiffstore(&mut self, dest: &str, keys: &[&str]) -> Result<i64, RedisError> {
+        self.sstore("SDIFFSTORE", dest, keys)
+    }
+
+    fn sstore(&mut self, command: &str, dest: &str, keys: &[&str]) -> Result<i64, RedisError> {
+        if keys.is_empty() {
+            return Err(RedisError::InvalidArgument(format!("{} requires at least one key", command)));
+        }
+
+        let cmd = keys.iter().fold(Cmd::cmd(command).key(dest), |cmd, key| cmd.key(key));
+
+        into_integer(cmd.run(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::RedisError;
+
+    #[test]
+    fn store_commands_return_cardinality() {
+        let server = MockServer::new(&[":1\r\n", ":5\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(1, client.sinterstore("dest", &["a", "b"]).unwrap());
+        assert_eq!(5, client.sunionstore("dest", &["a", "b", "c"]).unwrap());
+        assert_eq!(0, client.sdiffstore("dest", &["a"]).unwrap());
+        assert!(matches!(client.sinterstore("dest", &[]), Err(RedisError::InvalidArgument(_))));
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["SINTERSTORE", "dest", "a", "b"],
+                vec!["SUNIONSTORE", "dest", "a", "b", "c"],
+                vec!["SDIFFSTORE", "dest", "a"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn srandmember_positive_count_capped() {
+        let server = MockServer::new(&["*2\r\n$1\r\na\r\n$1\r\nb\r\n"]);
+        let mut client = server.client();
+
+        let mut members = client.srandmember("set", 5).unwrap();
+        members.sort();
+        assert_eq!(vec!["a", "b"], members);
+        drop(client);
+        assert_eq!(vec![vec!["SRANDMEMBER", "set", "5"]], server.commands());
+    }
+
+    #[test]
+    fn srandmember_negative_count_repeats() {
+        let server = MockServer::new(&["*4\r\n$1\r\na\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\na\r\n", "*1\r\n$1\r\na\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(vec!["a", "a", "b", "a"], client.srandmember("set", -4).unwrap());
+        assert!(client.srandmember("set", -4).is_err());
+        drop(client);
+        assert_eq!(vec!["SRANDMEMBER", "set", "-4"], server.commands()[0]);
+    }
+
+    #[test]
+    fn scard_of_set_and_missing_key() {
+        let server = MockServer::new(&[":2\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.scard("set").unwrap());
+        assert_eq!(0, client.scard("missing").unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["SCARD", "set"], vec!["SCARD", "missing"]], server.commands());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{Client, Cmd, RedisError};
+
+use super::{expect_ok, into_integer};
+
+///Which entries `XTRIM` evicts
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TrimStrategy {
+    ///Keep at most given count of newest entries
+    MaxLen(u64),
+    ///Evict entries with id lower than given one
+    MinId(String),
+}
+
+impl Client {
+    ///Delete entries of stream, returns count of deleted entries
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let deleted = client.xdel("events", &["1526919030474-55"]);
+    ///```
+    pub fn xdel(&mut self, key: &str, ids: &[&str]) -> Result<i64, RedisError> {
+        if ids.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("XDEL requires at least one id")));
+        }
+
+        into_integer(Cmd::cmd("XDEL").key(key).args(ids).run(self)?)
+    }
+
+    ///Evict old entries of stream, returns count of evicted entries
+    ///
+    ///Approximate trim evicts only whole macro nodes, it's much cheaper and may keep few more entries
+    ///```no_run
+    ///use redis_client::{Client, TrimStrategy};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let evicted = client.xtrim("events", TrimStrategy::MaxLen(1000), true);
+    ///```
+    pub fn xtrim(&mut self, key: &str, strategy: TrimStrategy, approximate: bool) -> Result<i64, RedisError> {
+        let (name, threshold) = match strategy {
+            TrimStrategy::MaxLen(len) => ("MAXLEN", len.to_string()),
+            TrimStrategy::MinId(id) => ("MINID", id),
+        };
+
+        let mut cmd = Cmd::cmd("XTRIM").key(key).arg(name);
+        if approximate {
+            cmd = cmd.arg("~");
+        }
+
+        into_integer(cmd.arg(&threshold).run(self)?)
+    }
+
+    ///Create consumer group of stream starting after entry `id`, `$` starts with new entries
+    ///
+    ///`mkstream` creates empty stream when key doesn't exist, otherwise missing stream is an error.
+    ///Existing group fails with `RedisError::Server` of code `BUSYGROUP`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.xgroup_create("events", "workers", "$", true).unwrap();
+    ///```
+    pub fn xgroup_create(&mut self, key: &str, group: &str, id: &str, mkstream: bool) -> Result<(), RedisError> {
+        let mut cmd = Cmd::cmd("XGROUP").arg("CREATE").key(key).arg(group).arg(id);
+        if mkstream {
+            cmd = cmd.arg("MKSTREAM");
+        }
+
+        expect_ok(cmd.run(self)?)
+    }
+
+    ///Create consumer group like `xgroup_create` treating existing group as success,
+    ///returns `false` when group already existed
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let created = client.xgroup_create_if_missing("events", "workers", "0", true).unwrap();
+    ///```
+    pub fn xgroup_create_if_missing(
+        &mut self,
+        key: &str,
+        group: &str,
+        id: &str,
+        mkstream: bool,
+    ) -> Result<bool, RedisError> {
+        match self.xgroup_create(key, group, id, mkstream) {
+            Ok(()) => Ok(true),
+            Err(RedisError::Server { ref code, .. }) if code == "BUSYGROUP" => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    ///Destroy consumer group with its pending entries, returns `false` if group didn't exist
+    pub fn xgroup_destroy(&mut self, key: &str, group: &str) -> Result<bool, RedisError> {
+        Ok(into_integer(Cmd::cmd("XGROUP").arg("DESTROY").key(key).arg(group).run(self)?)? == 1)
+    }
+
+    ///Create consumer in group, returns `false` if consumer already existed
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let created = client.xgroup_createconsumer("events", "workers", "worker-1").unwrap();
+    ///```
+    pub fn xgroup_createconsumer(&mut self, key: &str, group: &str, consumer: &str) -> Result<bool, RedisError> {
+        let cmd = Cmd::cmd("XGROUP").arg("CREATECONSUMER").key(key).arg(group).arg(consumer);
+
+        Ok(into_integer(cmd.run(self)?)? == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{RedisError, TrimStrategy};
+
+    #[test]
+    fn xtrim_encoding() {
+        let server = MockServer::new(&[":100\r\n", ":3\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(100, client.xtrim("events", TrimStrategy::MaxLen(1000), true).unwrap());
+        assert_eq!(3, client.xtrim("events", TrimStrategy::MinId(String::from("1526919030474-0")), false).unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["XTRIM", "events", "MAXLEN", "~", "1000"], vec!["XTRIM", "events", "MINID", "1526919030474-0"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn xdel_ids() {
+        let server = MockServer::new(&[":2\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.xdel("events", &["1-0", "2-0"]).unwrap());
+        assert!(client.xdel("events", &[]).is_err());
+        drop(client);
+        assert_eq!(vec![vec!["XDEL", "events", "1-0", "2-0"]], server.commands());
+    }
+
+    #[test]
+    fn xgroup_create_mkstream() {
+        let server = MockServer::new(&["+OK\r\n", "+OK\r\n"]);
+        let mut client = server.client();
+
+        client.xgroup_create("events", "workers", "$", true).unwrap();
+        client.xgroup_create("events", "audit", "0", false).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["XGROUP", "CREATE", "events", "workers", "$", "MKSTREAM"],
+                vec!["XGROUP", "CREATE", "events", "audit", "0"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn xgroup_busygroup() {
+        const BUSYGROUP: &str = "-BUSYGROUP Consumer Group name already exists\r\n";
+        const NO_KEY: &str = "-ERR The XGROUP subcommand requires the key to exist\r\n";
+        let server = MockServer::new(&[BUSYGROUP, BUSYGROUP, NO_KEY]);
+        let mut client = server.client();
+
+        match client.xgroup_create("events", "workers", "$", false) {
+            Err(RedisError::Server { code, .. }) => assert_eq!("BUSYGROUP", code),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        assert!(!client.xgroup_create_if_missing("events", "workers", "$", false).unwrap());
+        assert!(client.xgroup_create_if_missing("missing", "workers", "$", false).is_err());
+    }
+
+    #[test]
+    fn xgroup_destroy_and_createconsumer() {
+        let server = MockServer::new(&[":1\r\n", ":0\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        assert!(client.xgroup_createconsumer("events", "workers", "worker-1").unwrap());
+        assert!(!client.xgroup_createconsumer("events", "workers", "worker-1").unwrap());
+        assert!(client.xgroup_destroy("events", "workers").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["XGROUP", "CREATECONSUMER", "events", "workers", "worker-1"],
+                vec!["XGROUP", "CREATECONSUMER", "events", "workers", "worker-1"],
+                vec!["XGROUP", "DESTROY", "events", "workers"],
+            ],
+            server.commands()
+        );
+    }
+}
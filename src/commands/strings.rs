@@ -0,0 +1,577 @@
+use crate::{Client, Cmd, RedisError, Values};
+
+use super::args::{ArgWriter, FlagSpec};
+use super::{check, expect_ok, into_integer, into_optional_bytes, into_optional_string};
+
+static SET_FLAGS: FlagSpec = FlagSpec {
+    order: &["NX", "XX", "EX", "PX", "EXAT", "PXAT", "KEEPTTL"],
+    exclusive: &[&["NX", "XX"], &["EX", "PX", "EXAT", "PXAT", "KEEPTTL"]],
+};
+
+static GETEX_FLAGS: FlagSpec = FlagSpec {
+    order: &["EX", "PX", "EXAT", "PXAT", "PERSIST"],
+    exclusive: &[&["EX", "PX", "EXAT", "PXAT", "PERSIST"]],
+};
+
+///Operation of `BITOP` command
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    ///Inverts bits of exactly one source key
+    Not,
+}
+
+impl BitOp {
+    fn name(self) -> &'static str {
+        match self {
+            BitOp::And => "AND",
+            BitOp::Or => "OR",
+            BitOp::Xor => "XOR",
+            BitOp::Not => "NOT",
+        }
+    }
+}
+
+///Unit of range of `BITCOUNT`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BitUnit {
+    ///Range indexes bytes, default of server
+    Byte,
+    ///Range indexes bits, requires Redis 7
+    Bit,
+}
+
+impl BitUnit {
+    fn name(self) -> &'static str {
+        match self {
+            BitUnit::Byte => "BYTE",
+            BitUnit::Bit => "BIT",
+        }
+    }
+}
+
+///Options of `SET` command
+///```
+///use redis_client::SetOptions;
+///
+///let options = SetOptions::new().nx().ex(60);
+///```
+#[derive(Debug, Default, Clone)]
+pub struct SetOptions {
+    nx: bool,
+    xx: bool,
+    ex: Option<u64>,
+    px: Option<u64>,
+    exat: Option<u64>,
+    pxat: Option<u64>,
+    keep_ttl: bool,
+}
+
+impl SetOptions {
+    pub fn new() -> Self {
+        SetOptions::default()
+    }
+
+    ///Set only if key doesn't exist
+    pub fn nx(mut self) -> Self {
+        self.nx = true;
+
+        self
+    }
+
+    ///Set only if key exists
+    pub fn xx(mut self) -> Self {
+        self.xx = true;
+
+        self
+    }
+
+    ///Expire after seconds
+    pub fn ex(mut self, seconds: u64) -> Self {
+        self.ex = Some(seconds);
+
+        self
+    }
+
+    ///Expire after milliseconds
+    pub fn px(mut self, milliseconds: u64) -> Self {
+        self.px = Some(milliseconds);
+
+        self
+    }
+
+    ///Expire at Unix time in seconds
+    pub fn exat(mut self, timestamp: u64) -> Self {
+        self.exat = Some(timestamp);
+
+        self
+    }
+
+    ///Expire at Unix time in milliseconds
+    pub fn pxat(mut self, timestamp: u64) -> Self {
+        self.pxat = Some(timestamp);
+
+        self
+    }
+
+    ///Keep time to live of existing key
+    pub fn keep_ttl(mut self) -> Self {
+        self.keep_ttl = true;
+
+        self
+    }
+
+    fn write(&self, cmd: Cmd) -> Result<Cmd, RedisError> {
+        ArgWriter::new(&SET_FLAGS)
+            .flag("NX", self.nx)
+            .flag("XX", self.xx)
+            .flag_value("EX", self.ex)
+            .flag_value("PX", self.px)
+            .flag_value("EXAT", self.exat)
+            .flag_value("PXAT", self.pxat)
+            .flag("KEEPTTL", self.keep_ttl)
+            .write(cmd)
+    }
+}
+
+///Expiry change applied by `GETEX`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GetExExpiry {
+    ///Expire after seconds
+    Ex(u64),
+    ///Expire after milliseconds
+    Px(u64),
+    ///Expire at Unix time in seconds
+    ExAt(u64),
+    ///Expire at Unix time in milliseconds
+    PxAt(u64),
+    ///Remove time to live
+    Persist,
+}
+
+impl GetExExpiry {
+    fn write(expiry: Option<GetExExpiry>, cmd: Cmd) -> Result<Cmd, RedisError> {
+        let writer = ArgWriter::new(&GETEX_FLAGS);
+
+        match expiry {
+            Some(GetExExpiry::Ex(seconds)) => writer.flag_value("EX", Some(seconds)),
+            Some(GetExExpiry::Px(milliseconds)) => writer.flag_value("PX", Some(milliseconds)),
+            Some(GetExExpiry::ExAt(timestamp)) => writer.flag_value("EXAT", Some(timestamp)),
+            Some(GetExExpiry::PxAt(timestamp)) => writer.flag_value("PXAT", Some(timestamp)),
+            Some(GetExExpiry::Persist) => writer.flag("PERSIST", true),
+            None => writer,
+        }.write(cmd)
+    }
+}
+
+impl Client {
+    ///Set string value of key
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set("key", "value").unwrap();
+    ///```
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), RedisError> {
+        expect_ok(Cmd::cmd("SET").key(key).arg(value).run(self)?)
+    }
+
+    ///Get string value of key, `None` if key is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let value = client.get("key");
+    ///```
+    pub fn get(&mut self, key: &str) -> Result<Option<String>, RedisError> {
+        into_optional_string(Cmd::cmd("GET").key(key).run(self)?)
+    }
+
+    ///Get raw value of key, for values which aren't UTF-8
+    pub fn get_bytes(&mut self, key: &str) -> Result<Option<Vec<u8>>, RedisError> {
+        into_optional_bytes(Cmd::cmd("GET").key(key).run(self)?)
+    }
+
+    ///Get value of key and delete it in one atomic command, missing key gives `None`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let token = client.getdel("one_time_token");
+    ///```
+    pub fn getdel(&mut self, key: &str) -> Result<Option<String>, RedisError> {
+        into_optional_string(Cmd::cmd("GETDEL").key(key).run(self)?)
+    }
+
+    ///Get raw value of key and delete it
+    pub fn getdel_bytes(&mut self, key: &str) -> Result<Option<Vec<u8>>, RedisError> {
+        into_optional_bytes(Cmd::cmd("GETDEL").key(key).run(self)?)
+    }
+
+    ///Set string value of key with options, returns `false` if `NX`/`XX` condition isn't met
+    ///```no_run
+    ///use redis_client::{Client, SetOptions};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let locked = client.set_options("lock", "owner", &SetOptions::new().nx().px(30_000));
+    ///```
+    pub fn set_options(&mut self, key: &str, value: &str, options: &SetOptions) -> Result<bool, RedisError> {
+        let cmd = options.write(Cmd::cmd("SET").key(key).arg(value))?;
+
+        match check(cmd.run(self)?)? {
+            value if value.is_ok() => Ok(true),
+            Values::Nil => Ok(false),
+            other => Err(RedisError::unexpected_reply("OK", &other)),
+        }
+    }
+
+    ///Get value of key and optionally change its expiry, without expiry it's pure read
+    ///
+    ///Value is read and expiry is changed by one atomic command, missing key gives `None` and nothing is changed
+    ///```no_run
+    ///use redis_client::{Client, GetExExpiry};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let value = client.getex("session", Some(GetExExpiry::Ex(600)));
+    ///```
+    pub fn getex(&mut self, key: &str, expiry: Option<GetExExpiry>) -> Result<Option<String>, RedisError> {
+        let cmd = GetExExpiry::write(expiry, Cmd::cmd("GETEX").key(key))?;
+
+        into_optional_string(cmd.run(self)?)
+    }
+
+    ///Get raw value of key and optionally change its expiry
+    pub fn getex_bytes(&mut self, key: &str, expiry: Option<GetExExpiry>) -> Result<Option<Vec<u8>>, RedisError> {
+        let cmd = GetExExpiry::write(expiry, Cmd::cmd("GETEX").key(key))?;
+
+        into_optional_bytes(cmd.run(self)?)
+    }
+
+    ///Overwrite part of value starting at `offset`, returns length of value after write
+    ///
+    ///Value shorter than `offset`, or missing key, is padded with zero bytes up to `offset`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let len = client.setrange("greeting", 6, "redis").unwrap();
+    ///```
+    pub fn setrange(&mut self, key: &str, offset: u64, value: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("SETRANGE").key(key).arg(&offset.to_string()).arg(value).run(self)?)
+    }
+
+    ///Increment integer value of key by one, missing key counts as 0
+    pub fn incr(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("INCR").key(key).run(self)?)
+    }
+
+    ///Increment integer value of key by `delta`, returns new value
+    ///
+    ///Overflow of 64 bit integer is rejected by server and returned as `RedisError::Server`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let visits = client.incr_by("visits", 10).unwrap();
+    ///```
+    pub fn incr_by(&mut self, key: &str, delta: i64) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("INCRBY").key(key).arg(&delta.to_string()).run(self)?)
+    }
+
+    ///Decrement integer value of key by one, missing key counts as 0
+    pub fn decr(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("DECR").key(key).run(self)?)
+    }
+
+    ///Decrement integer value of key by `delta`, returns new value
+    ///
+    ///Overflow of 64 bit integer is rejected by server and returned as `RedisError::Server`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let stock = client.decr_by("stock", 3).unwrap();
+    ///```
+    pub fn decr_by(&mut self, key: &str, delta: i64) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("DECRBY").key(key).arg(&delta.to_string()).run(self)?)
+    }
+
+    ///Set several keys at once
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.mset(&[("first", "1"), ("second", "2")]).unwrap();
+    ///```
+    pub fn mset(&mut self, pairs: &[(&str, &str)]) -> Result<(), RedisError> {
+        if pairs.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("MSET requires at least one key")));
+        }
+
+        let cmd = pairs.iter().fold(Cmd::cmd("MSET"), |cmd, (key, value)| cmd.key(key).arg(value));
+
+        expect_ok(cmd.run(self)?)
+    }
+
+    ///Store result of bitwise operation on source keys in `dest`, returns length of result string
+    ///```no_run
+    ///use redis_client::{BitOp, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let len = client.bitop(BitOp::And, "active_both", &["active:mon", "active:tue"]).unwrap();
+    ///```
+    pub fn bitop(&mut self, op: BitOp, dest: &str, sources: &[&str]) -> Result<i64, RedisError> {
+        match (op, sources.len()) {
+            (_, 0) => return Err(RedisError::InvalidArgument(String::from("BITOP requires at least one source key"))),
+            (BitOp::Not, n) if n > 1 => {
+                return Err(RedisError::InvalidArgument(String::from("BITOP NOT accepts exactly one source key")));
+            },
+            _ => {},
+        }
+
+        let cmd = sources.iter().fold(Cmd::cmd("BITOP").arg(op.name()).key(dest), |cmd, source| cmd.key(source));
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Count set bits of string value, missing key has none
+    pub fn bitcount(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("BITCOUNT").key(key).run(self)?)
+    }
+
+    ///Count set bits between `start` and `end` inclusive, negative indexes count from end of string
+    ///
+    ///`BitUnit::Bit` indexes single bits instead of whole bytes
+    ///```no_run
+    ///use redis_client::{BitUnit, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let first_byte = client.bitcount_range("active", 0, 0, BitUnit::Byte).unwrap();
+    ///let first_bits = client.bitcount_range("active", 0, 4, BitUnit::Bit).unwrap();
+    ///```
+    pub fn bitcount_range(&mut self, key: &str, start: i64, end: i64, unit: BitUnit) -> Result<i64, RedisError> {
+        let cmd = Cmd::cmd("BITCOUNT").key(key).arg(&start.to_string()).arg(&end.to_string()).arg(unit.name());
+
+        into_integer(cmd.run(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{BitOp, BitUnit, Client, Cmd, GetExExpiry, RedisError, SetOptions};
+
+    #[test]
+    fn bitop_and() {
+        let server = MockServer::new(&[":4\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(4, client.bitop(BitOp::And, "dest", &["first", "second"]).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["BITOP", "AND", "dest", "first", "second"]], server.commands());
+    }
+
+    #[test]
+    fn bitop_not_single_source() {
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+
+        assert!(matches!(client.bitop(BitOp::Not, "dest", &["first", "second"]), Err(RedisError::InvalidArgument(_))));
+        assert!(matches!(client.bitop(BitOp::Or, "dest", &[]), Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn bitcount_range_units() {
+        let server = MockServer::new(&[":6\r\n", ":17\r\n", ":26\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(6, client.bitcount_range("mykey", 1, 1, BitUnit::Byte).unwrap());
+        assert_eq!(17, client.bitcount_range("mykey", 5, -1, BitUnit::Bit).unwrap());
+        assert_eq!(26, client.bitcount("mykey").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["BITCOUNT", "mykey", "1", "1", "BYTE"],
+                vec!["BITCOUNT", "mykey", "5", "-1", "BIT"],
+                vec!["BITCOUNT", "mykey"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn bitcount_known_pattern() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        client.set("bitcount_pattern", "foobar").unwrap();
+
+        assert_eq!(26, client.bitcount("bitcount_pattern").unwrap());
+        assert_eq!(4, client.bitcount_range("bitcount_pattern", 0, 0, BitUnit::Byte).unwrap());
+        assert_eq!(6, client.bitcount_range("bitcount_pattern", 1, 1, BitUnit::Byte).unwrap());
+        assert_eq!(17, client.bitcount_range("bitcount_pattern", 5, 30, BitUnit::Bit).unwrap());
+    }
+
+    #[test]
+    fn getters_map_both_nulls_to_none() {
+        for null in &["$-1\r\n", "_\r\n"] {
+            let server = MockServer::new(&[*null; 6]);
+            let mut client = server.client();
+
+            assert_eq!(None, client.get("key").unwrap());
+            assert_eq!(None, client.get_bytes("key").unwrap());
+            assert_eq!(None, client.getdel("key").unwrap());
+            assert_eq!(None, client.getdel_bytes("key").unwrap());
+            assert_eq!(None, client.getex("key", None).unwrap());
+            assert_eq!(None, client.getex_bytes("key", Some(GetExExpiry::Persist)).unwrap());
+        }
+    }
+
+    #[test]
+    fn getex_persist_and_getdel() {
+        let server = MockServer::new(&["$5\r\nvalue\r\n", ":-1\r\n", "$5\r\nvalue\r\n", "$-1\r\n", "$-1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(String::from("value")), client.getex("key", Some(GetExExpiry::Persist)).unwrap());
+        assert_eq!(-1, client.ttl("key").unwrap());
+        assert_eq!(Some(String::from("value")), client.getdel("key").unwrap());
+        assert_eq!(None, client.get("key").unwrap());
+        assert_eq!(None, client.getdel("key").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["GETEX", "key", "PERSIST"],
+                vec!["TTL", "key"],
+                vec!["GETDEL", "key"],
+                vec!["GET", "key"],
+                vec!["GETDEL", "key"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn getex_persist_removes_ttl() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        Cmd::cmd("SET").arg("test_getex_persist").arg("value").arg("EX").arg("100").execute(&mut client).unwrap();
+
+        assert_eq!(Some(String::from("value")), client.getex("test_getex_persist", None).unwrap());
+        assert!(client.ttl("test_getex_persist").unwrap() > 0);
+        assert_eq!(Some(String::from("value")), client.getex("test_getex_persist", Some(GetExExpiry::Persist)).unwrap());
+        assert_eq!(-1, client.ttl("test_getex_persist").unwrap());
+        assert_eq!(Some(String::from("value")), client.getdel("test_getex_persist").unwrap());
+        assert_eq!(-2, client.ttl("test_getex_persist").unwrap());
+        assert_eq!(None, client.getex("test_getex_persist", Some(GetExExpiry::Persist)).unwrap());
+    }
+
+    #[test]
+    fn decr_by_returns_new_value() {
+        let server = MockServer::new(&[":7\r\n", ":8\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(7, client.decr_by("stock", 3).unwrap());
+        assert_eq!(8, client.incr_by("stock", 1).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["DECRBY", "stock", "3"], vec!["INCRBY", "stock", "1"]], server.commands());
+    }
+
+    #[test]
+    fn decr_by_overflow_keeps_server_message() {
+        let server = MockServer::new(&["-ERR increment or decrement would overflow\r\n"]);
+        let mut client = server.client();
+
+        match client.decr_by("counter", i64::MAX) {
+            Err(RedisError::Server { code, message }) => {
+                assert_eq!("ERR", code);
+                assert_eq!("increment or decrement would overflow", message);
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+        drop(client);
+        assert_eq!(vec![vec!["DECRBY", "counter", &i64::MAX.to_string()]], server.commands());
+    }
+
+    #[test]
+    fn setrange_pads_missing_key() {
+        let server = MockServer::new(&[":10\r\n", "$10\r\n\u{0}\u{0}\u{0}\u{0}\u{0}hello\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(5 + "hello".len() as i64, client.setrange("missing", 5, "hello").unwrap());
+        assert_eq!(Some(b"\0\0\0\0\0hello".to_vec()), client.get_bytes("missing").unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["SETRANGE", "missing", "5", "hello"], vec!["GET", "missing"]], server.commands());
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn setrange_grows_value() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        Cmd::cmd("DEL").arg("test_setrange").execute(&mut client).unwrap();
+
+        assert_eq!(10, client.setrange("test_setrange", 5, "hello").unwrap());
+        assert_eq!(Some(b"\0\0\0\0\0hello".to_vec()), client.get_bytes("test_setrange").unwrap());
+        assert_eq!(12, client.setrange("test_setrange", 10, "!!").unwrap());
+    }
+
+    #[test]
+    fn binary_getters() {
+        let server = MockServer::new(&["$2\r\n\u{7f}\u{0}\r\n", "$5\r\nvalue\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(vec![0x7f, 0x00]), client.getdel_bytes("key").unwrap());
+        assert_eq!(Some(b"value".to_vec()), client.getex_bytes("key", None).unwrap());
+    }
+
+    #[test]
+    fn set_with_options() {
+        let server = MockServer::new(&["+OK\r\n", "$-1\r\n"]);
+        let mut client = server.client();
+
+        assert!(client.set_options("key", "value", &SetOptions::new().px(100).nx()).unwrap());
+        assert!(!client.set_options("key", "value", &SetOptions::new().xx().keep_ttl()).unwrap());
+        assert!(client.set_options("key", "value", &SetOptions::new().ex(1).keep_ttl()).is_err());
+        drop(client);
+        assert_eq!(
+            vec![vec!["SET", "key", "value", "NX", "PX", "100"], vec!["SET", "key", "value", "XX", "KEEPTTL"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn getex_with_expiry() {
+        let server = MockServer::new(&["$5\r\nvalue\r\n", "$5\r\nvalue\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(String::from("value")), client.getex("key", Some(GetExExpiry::ExAt(1893456000))).unwrap());
+        assert_eq!(Some(String::from("value")), client.getex("key", None).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["GETEX", "key", "EXAT", "1893456000"], vec!["GETEX", "key"]], server.commands());
+    }
+
+    #[test]
+    fn set_accepts_ok() {
+        let server = MockServer::new(&["+OK\r\n", "+OK\r\n"]);
+        let mut client = server.client();
+
+        client.set("key", "value").unwrap();
+        client.mset(&[("first", "1"), ("second", "2")]).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![vec!["SET", "key", "value"], vec!["MSET", "first", "1", "second", "2"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn set_rejects_queued_outside_transaction() {
+        let server = MockServer::new(&["+QUEUED\r\n"]);
+        let mut client = server.client();
+
+        match client.set("key", "value") {
+            Err(RedisError::UnexpectedReply { expected, .. }) => assert_eq!("OK", expected),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,561 @@
+use std::ops::Bound;
+
+use crate::{Client, Cmd, FromRedisValue, RedisError, Values};
+
+use super::args::{ArgWriter, FlagSpec};
+use super::{check, into_array, into_integer, into_strings};
+
+static ZADD_FLAGS: FlagSpec = FlagSpec {
+    order: &["NX", "XX", "GT", "LT", "CH"],
+    exclusive: &[&["NX", "XX"], &["NX", "GT"], &["NX", "LT"], &["GT", "LT"]],
+};
+
+///Options of `ZADD` command
+///```
+///use redis_client::ZAddOptions;
+///
+///let options = ZAddOptions::new().xx().gt().ch();
+///```
+#[derive(Debug, Default, Clone)]
+pub struct ZAddOptions {
+    nx: bool,
+    xx: bool,
+    gt: bool,
+    lt: bool,
+    ch: bool,
+}
+
+impl ZAddOptions {
+    pub fn new() -> Self {
+        ZAddOptions::default()
+    }
+
+    ///Only add new members
+    pub fn nx(mut self) -> Self {
+        self.nx = true;
+
+        self
+    }
+
+    ///Only update existing members
+    pub fn xx(mut self) -> Self {
+        self.xx = true;
+
+        self
+    }
+
+    ///Only update score if new one is greater
+    pub fn gt(mut self) -> Self {
+        self.gt = true;
+
+        self
+    }
+
+    ///Only update score if new one is less
+    pub fn lt(mut self) -> Self {
+        self.lt = true;
+
+        self
+    }
+
+    ///Return count of changed members instead of added ones
+    pub fn ch(mut self) -> Self {
+        self.ch = true;
+
+        self
+    }
+
+    fn write(&self, cmd: Cmd) -> Result<Cmd, RedisError> {
+        ArgWriter::new(&ZADD_FLAGS)
+            .flag("NX", self.nx)
+            .flag("XX", self.xx)
+            .flag("GT", self.gt)
+            .flag("LT", self.lt)
+            .flag("CH", self.ch)
+            .write(cmd)
+    }
+}
+
+///How scores of member present in several sets are combined by `ZUNIONSTORE` and `ZINTERSTORE`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn as_arg(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "SUM",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+        }
+    }
+}
+
+///Encode score argument the way Redis parses it, infinities become `+inf` and `-inf`
+///
+///`Display` of `f64` doesn't depend on locale and never uses scientific notation
+///```
+///use redis_client::to_redis_score_arg;
+///
+///assert_eq!("3.5", to_redis_score_arg(3.5));
+///assert_eq!("+inf", to_redis_score_arg(f64::INFINITY));
+///```
+pub fn to_redis_score_arg(score: f64) -> String {
+    if score == f64::INFINITY {
+        String::from("+inf")
+    } else if score == f64::NEG_INFINITY {
+        String::from("-inf")
+    } else {
+        score.to_string()
+    }
+}
+
+fn format_score(score: f64) -> Result<String, RedisError> {
+    if score.is_nan() {
+        return Err(RedisError::InvalidArgument(String::from("Score can't be NaN")));
+    }
+
+    Ok(to_redis_score_arg(score))
+}
+
+///Convert members with scores, flat array of RESP2 or array of pairs of RESP3
+fn into_scored_members(value: Values) -> Result<Vec<(String, f64)>, RedisError> {
+    let mut values = into_array(value)?;
+    if let Some(Values::Arrays(_)) = values.first() {
+        values = values.into_iter().map(into_array).collect::<Result<Vec<_>, _>>()?.into_iter().flatten().collect();
+    }
+    if values.len() % 2 != 0 {
+        return Err(RedisError::Protocol(format!("Odd count {} of member score elements", values.len())));
+    }
+
+    let mut values = values.into_iter();
+    let mut members = Vec::with_capacity(values.len() / 2);
+    while let (Some(member), Some(score)) = (values.next(), values.next()) {
+        members.push((String::from_redis_value(member)?, f64::from_redis_value(score)?));
+    }
+
+    Ok(members)
+}
+
+///Encode score bound, `(` marks exclusive bound
+fn score_bound(bound: Bound<f64>, unbounded: &str) -> String {
+    match bound {
+        Bound::Included(score) => to_redis_score_arg(score),
+        Bound::Excluded(score) => format!("({}", to_redis_score_arg(score)),
+        Bound::Unbounded => unbounded.to_string(),
+    }
+}
+
+impl Client {
+    ///Add member with score to sorted set, returns count of added members
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.zadd("leaderboard", 10.5, "player").unwrap();
+    ///```
+    pub fn zadd(&mut self, key: &str, score: f64, member: &str) -> Result<i64, RedisError> {
+        self.zadd_options(key, score, member, &ZAddOptions::new())
+    }
+
+    ///Add member with score to sorted set with options, returns `1` if member was added or with `ch` if it was changed
+    pub fn zadd_options(&mut self, key: &str, score: f64, member: &str, options: &ZAddOptions) -> Result<i64, RedisError> {
+        let cmd = options.write(Cmd::cmd("ZADD").key(key))?.arg(&format_score(score)?).arg(member);
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Add several members with scores in one command, returns count of added members
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.zadd_multi("leaderboard", &[(10.0, "alice"), (7.5, "bob")]).unwrap();
+    ///```
+    pub fn zadd_multi(&mut self, key: &str, items: &[(f64, &str)]) -> Result<i64, RedisError> {
+        self.zadd_multi_options(key, items, &ZAddOptions::new())
+    }
+
+    ///Add several members with scores in one command with options, returns count of added members
+    ///or with `ch` count of changed ones, added and updated
+    ///```no_run
+    ///use redis_client::{Client, ZAddOptions};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let changed = client.zadd_multi_options("leaderboard", &[(11.0, "alice"), (3.0, "carol")], &ZAddOptions::new().ch());
+    ///```
+    pub fn zadd_multi_options(&mut self, key: &str, items: &[(f64, &str)], options: &ZAddOptions) -> Result<i64, RedisError> {
+        if items.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("ZADD requires at least one member")));
+        }
+
+        let mut cmd = options.write(Cmd::cmd("ZADD").key(key))?;
+        for (score, member) in items {
+            cmd = cmd.arg(&format_score(*score)?).arg(member);
+        }
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Add `delta` to score of member and return new score, missing member is added with score `delta`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let score = client.zincrby("leaderboard", 2.5, "player").unwrap();
+    ///```
+    pub fn zincrby(&mut self, key: &str, delta: f64, member: &str) -> Result<f64, RedisError> {
+        let cmd = Cmd::cmd("ZINCRBY").key(key).arg(&format_score(delta)?).arg(member);
+
+        f64::from_redis_value(check(cmd.run(self)?)?)
+    }
+
+    ///Store union of sorted sets in `dest`, scores are multiplied by weights of their sets. Returns count of members in `dest`
+    ///```no_run
+    ///use redis_client::{Aggregate, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.zunionstore("total", &["week1", "week2"], Some(&[1.0, 2.0]), Some(Aggregate::Max)).unwrap();
+    ///```
+    pub fn zunionstore(
+        &mut self,
+        dest: &str,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Option<Aggregate>,
+    ) -> Result<i64, RedisError> {
+        self.zstore("ZUNIONSTORE", dest, keys, weights, aggregate)
+    }
+
+    ///Store intersection of sorted sets in `dest`, same arguments as `zunionstore`
+    pub fn zinterstore(
+        &mut self,
+        dest: &str,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Option<Aggregate>,
+    ) -> Result<i64, RedisError> {
+        self.zstore("ZINTERSTORE", dest, keys, weights, aggregate)
+    }
+
+    ///Get members of first sorted set missing in all others
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let only_first = client.zdiff(&["players:2023", "players:2024"]).unwrap();
+    ///```
+    pub fn zdiff(&mut self, keys: &[&str]) -> Result<Vec<String>, RedisError> {
+        into_strings(Self::zdiff_cmd(keys)?.run(self)?)
+    }
+
+    ///Get members with scores of first sorted set missing in all others
+    pub fn zdiff_withscores(&mut self, keys: &[&str]) -> Result<Vec<(String, f64)>, RedisError> {
+        into_scored_members(Self::zdiff_cmd(keys)?.arg("WITHSCORES").run(self)?)
+    }
+
+    ///Store difference of first sorted set and all others in `dest`, returns count of members in `dest`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.zdiffstore("churned", &["players:2023", "players:2024"]).unwrap();
+    ///```
+    pub fn zdiffstore(&mut self, dest: &str, keys: &[&str]) -> Result<i64, RedisError> {
+        self.zstore("ZDIFFSTORE", dest, keys, None, None)
+    }
+
+    fn zdiff_cmd(keys: &[&str]) -> Result<Cmd, RedisError> {
+        if keys.is_empty() {
+            return Err(RedisError::InvalidArgument(String::from("ZDIFF requires at least one key")));
+        }
+
+        Ok(keys.iter().fold(Cmd::cmd("ZDIFF").arg(&keys.len().to_string()), |cmd, key| cmd.key(key)))
+    }
+
+    fn zstore(
+        &mut self,
+        command: &str,
+        dest: &str,
+        keys: &[&str],
+        weights: Option<&[f64]>,
+        aggregate: Option<Aggregate>,
+    ) -> Result<i64, RedisError> {
+        if keys.is_empty() {
+            return Err(RedisError::InvalidArgument(format!("{} requires at least one key", command)));
+        }
+
+        let mut cmd = Cmd::cmd(command).key(dest).arg(&keys.len().to_string());
+        for key in keys {
+            cmd = cmd.key(key);
+        }
+
+        if let Some(weights) = weights {
+            if weights.len() != keys.len() {
+                return Err(RedisError::InvalidArgument(format!(
+                    "{} weights given for {} keys",
+                    weights.len(),
+                    keys.len()
+                )));
+            }
+
+            cmd = cmd.arg("WEIGHTS");
+            for weight in weights {
+                cmd = cmd.arg(&format_score(*weight)?);
+            }
+        }
+        if let Some(aggregate) = aggregate {
+            cmd = cmd.arg("AGGREGATE").arg(aggregate.as_arg());
+        }
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Get count of sorted set members, missing key is empty sorted set
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.zcard("leaderboard");
+    ///```
+    pub fn zcard(&mut self, key: &str) -> Result<i64, RedisError> {
+        into_integer(Cmd::cmd("ZCARD").key(key).run(self)?)
+    }
+
+    ///Get count of members with score in range
+    ///```no_run
+    ///use std::ops::Bound;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.zcount("leaderboard", Bound::Excluded(10.0), Bound::Unbounded);
+    ///```
+    pub fn zcount(&mut self, key: &str, min: Bound<f64>, max: Bound<f64>) -> Result<i64, RedisError> {
+        let cmd = Cmd::cmd("ZCOUNT").key(key).arg(&score_bound(min, "-inf")).arg(&score_bound(max, "+inf"));
+
+        into_integer(cmd.run(self)?)
+    }
+
+    ///Get rank of member ordered by score from lowest, `None` if member is missing
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let rank = client.zrank("leaderboard", "player");
+    ///```
+    pub fn zrank(&mut self, key: &str, member: &str) -> Result<Option<i64>, RedisError> {
+        Option::from_redis_value(check(Cmd::cmd("ZRANK").key(key).arg(member).run(self)?)?)
+    }
+
+    ///Get rank of member ordered by score from highest, `None` if member is missing
+    pub fn zrevrank(&mut self, key: &str, member: &str) -> Result<Option<i64>, RedisError> {
+        Option::from_redis_value(check(Cmd::cmd("ZREVRANK").key(key).arg(member).run(self)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound;
+
+    use super::to_redis_score_arg;
+    use crate::mock::MockServer;
+    use crate::{Aggregate, RedisError, ZAddOptions};
+
+    #[test]
+    fn zadd_with_options() {
+        let server = MockServer::new(&[":1\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(1, client.zadd("zset", 1.5, "a").unwrap());
+        assert_eq!(1, client.zadd_options("zset", 2.0, "a", &ZAddOptions::new().ch().gt().xx()).unwrap());
+        assert!(client.zadd_options("zset", 2.0, "a", &ZAddOptions::new().nx().lt()).is_err());
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZADD", "zset", "1.5", "a"], vec!["ZADD", "zset", "XX", "GT", "CH", "2", "a"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn score_arg_encoding() {
+        assert_eq!("3.5", to_redis_score_arg(3.5));
+        assert_eq!("+inf", to_redis_score_arg(f64::INFINITY));
+        assert_eq!("-inf", to_redis_score_arg(f64::NEG_INFINITY));
+        assert_eq!("1000000", to_redis_score_arg(1_000_000.0));
+    }
+
+    #[test]
+    fn infinite_scores_in_commands() {
+        let server = MockServer::new(&[":2\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        client.zadd_multi("zset", &[(f64::INFINITY, "top"), (f64::NEG_INFINITY, "bottom")]).unwrap();
+        client.zcount("zset", Bound::Excluded(f64::NEG_INFINITY), Bound::Included(3.5)).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZADD", "zset", "+inf", "top", "-inf", "bottom"], vec!["ZCOUNT", "zset", "(-inf", "3.5"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn zadd_multi_with_ch_counts_changed() {
+        let server = MockServer::new(&[":2\r\n", ":2\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.zadd_multi("zset", &[(1.0, "a"), (2.0, "b")]).unwrap());
+        assert_eq!(2, client.zadd_multi_options("zset", &[(5.0, "a"), (3.0, "c")], &ZAddOptions::new().ch()).unwrap());
+        assert_eq!(1, client.zadd_multi("zset", &[(7.0, "a"), (4.0, "d")]).unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["ZADD", "zset", "1", "a", "2", "b"],
+                vec!["ZADD", "zset", "CH", "5", "a", "3", "c"],
+                vec!["ZADD", "zset", "7", "a", "4", "d"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn zadd_multi_ch_on_server() {
+        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+        crate::Cmd::cmd("DEL").arg("test_zadd_ch").execute(&mut client).unwrap();
+        client.zadd_multi("test_zadd_ch", &[(1.0, "a"), (2.0, "b")]).unwrap();
+
+        let changed = client.zadd_multi_options("test_zadd_ch", &[(5.0, "a"), (3.0, "c")], &ZAddOptions::new().ch());
+        let added = client.zadd_multi("test_zadd_ch", &[(7.0, "a"), (4.0, "d")]);
+        assert_eq!(2, changed.unwrap());
+        assert_eq!(1, added.unwrap());
+    }
+
+    #[test]
+    fn zadd_multi_in_one_command() {
+        let server = MockServer::new(&[":3\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(3, client.zadd_multi("zset", &[(1.5, "a"), (0.0000001, "b"), (12345678901234567.0, "c")]).unwrap());
+        assert!(client.zadd_multi("zset", &[]).is_err());
+        assert!(client.zadd_multi("zset", &[(f64::NAN, "a")]).is_err());
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZADD", "zset", "1.5", "a", "0.0000001", "b", "12345678901234568", "c"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn zcount_score_range() {
+        let server = MockServer::new(&[":3\r\n", ":5\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(3, client.zcount("zset", Bound::Included(1.5), Bound::Excluded(10.0)).unwrap());
+        assert_eq!(5, client.zcount("zset", Bound::Unbounded, Bound::Unbounded).unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZCOUNT", "zset", "1.5", "(10"], vec!["ZCOUNT", "zset", "-inf", "+inf"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn zrank_present_and_absent_member() {
+        let server = MockServer::new(&[":2\r\n", "$-1\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(Some(2), client.zrank("zset", "a").unwrap());
+        assert_eq!(None, client.zrank("zset", "missing").unwrap());
+        assert_eq!(Some(0), client.zrevrank("zset", "a").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZRANK", "zset", "a"], vec!["ZRANK", "zset", "missing"], vec!["ZREVRANK", "zset", "a"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn zincrby_accumulates_score() {
+        let server = MockServer::new(&["$3\r\n1.5\r\n", "$4\r\n3.75\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(1.5, client.zincrby("zset", 1.5, "new").unwrap());
+        assert_eq!(3.75, client.zincrby("zset", 2.25, "new").unwrap());
+        assert!(client.zincrby("zset", f64::NAN, "new").is_err());
+        drop(client);
+        assert_eq!(vec![vec!["ZINCRBY", "zset", "1.5", "new"], vec!["ZINCRBY", "zset", "2.25", "new"]], server.commands());
+    }
+
+    #[test]
+    fn zstore_with_weights_and_aggregate() {
+        let server = MockServer::new(&[":3\r\n", ":1\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(3, client.zunionstore("dest", &["a", "b"], Some(&[1.0, 2.5]), Some(Aggregate::Max)).unwrap());
+        assert_eq!(1, client.zinterstore("dest", &["a", "b"], None, None).unwrap());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["ZUNIONSTORE", "dest", "2", "a", "b", "WEIGHTS", "1", "2.5", "AGGREGATE", "MAX"],
+                vec!["ZINTERSTORE", "dest", "2", "a", "b"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn zstore_weights_mismatch() {
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+
+        let result = client.zinterstore("dest", &["a", "b"], Some(&[1.0]), Some(Aggregate::Sum));
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        assert!(client.zunionstore("dest", &[], None, None).is_err());
+    }
+
+    #[test]
+    fn zdiff_with_and_without_scores() {
+        let server = MockServer::new(&[
+            "*2\r\n$1\r\na\r\n$1\r\nb\r\n",
+            "*4\r\n$1\r\na\r\n$1\r\n1\r\n$1\r\nb\r\n$3\r\n2.5\r\n",
+            "*2\r\n*2\r\n$1\r\na\r\n,1\r\n*2\r\n$1\r\nb\r\n,2.5\r\n",
+        ]);
+        let mut client = server.client();
+        let scored = vec![(String::from("a"), 1.0), (String::from("b"), 2.5)];
+
+        assert_eq!(vec!["a", "b"], client.zdiff(&["first", "second"]).unwrap());
+        assert_eq!(scored, client.zdiff_withscores(&["first", "second"]).unwrap());
+        assert_eq!(scored, client.zdiff_withscores(&["first", "second"]).unwrap());
+        assert!(matches!(client.zdiff(&[]), Err(RedisError::InvalidArgument(_))));
+        drop(client);
+        assert_eq!(
+            vec![vec!["ZDIFF", "2", "first", "second"], vec!["ZDIFF", "2", "first", "second", "WITHSCORES"]],
+            server.commands()[..2].to_vec()
+        );
+    }
+
+    #[test]
+    fn zdiffstore_returns_cardinality() {
+        let server = MockServer::new(&[":2\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.zdiffstore("dest", &["first", "second"]).unwrap());
+        assert!(matches!(client.zdiffstore("dest", &[]), Err(RedisError::InvalidArgument(_))));
+        drop(client);
+        assert_eq!(vec![vec!["ZDIFFSTORE", "dest", "2", "first", "second"]], server.commands());
+    }
+
+    #[test]
+    fn zcard_of_sorted_set_and_missing_key() {
+        let server = MockServer::new(&[":4\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(4, client.zcard("zset").unwrap());
+        assert_eq!(0, client.zcard("missing").unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["ZCARD", "zset"], vec!["ZCARD", "missing"]], server.commands());
+    }
+}
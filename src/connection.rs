@@ -0,0 +1,447 @@
+use std::{
+    fmt,
+    io::{self, ErrorKind, Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs},
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
+
+///Transport of client, implemented for TCP and Unix sockets
+///
+///Custom transport is passed to `Client::from_connection`, its `reconnect` is called when connection is lost
+///```no_run
+///use redis_client::{Client, TcpConnection};
+///
+///let client = Client::from_connection(TcpConnection::connect("127.0.0.1:6379").unwrap());
+///```
+pub trait Connection: Read + Write + Send {
+    ///Open connection to same server again, client sets timeouts of lost connection on it afterwards
+    fn reconnect(&mut self) -> io::Result<()>;
+
+    ///Timeout of reads, `None` blocks forever
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    ///Set timeout of reads, transport without timeouts ignores it
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let _ = timeout;
+
+        Ok(())
+    }
+
+    ///Timeout of writes, `None` blocks forever
+    fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    ///Set timeout of writes, transport without timeouts ignores it
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let _ = timeout;
+
+        Ok(())
+    }
+
+    ///Handle to same connection, used to close it from other thread
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Err(io::Error::new(ErrorKind::Unsupported, "Connection can't be cloned"))
+    }
+
+    ///Close connection unblocking pending reads
+    fn shutdown(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+///TCP transport remembering address of server, socket reset by peer can't tell it anymore
+#[derive(Debug)]
+pub struct TcpConnection {
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+}
+
+impl TcpConnection {
+    ///Wrap connected stream, fails if it's already disconnected
+    pub fn new(stream: TcpStream) -> io::Result<Self> {
+        let peer_addr = stream.peer_addr()?;
+
+        Ok(TcpConnection { stream, peer_addr })
+    }
+
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        TcpConnection::new(TcpStream::connect(addr)?)
+    }
+}
+
+impl Read for TcpConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for TcpConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Connection for TcpConnection {
+    ///Connect to address of server stored when connection was created
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = TcpStream::connect(self.peer_addr)?;
+
+        Ok(())
+    }
+
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.stream.read_timeout()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.stream.write_timeout()
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_write_timeout(timeout)
+    }
+
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(TcpConnection { stream: self.stream.try_clone()?, peer_addr: self.peer_addr }))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Both)
+    }
+}
+
+///Unix socket transport remembering socket path of server
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct UnixConnection {
+    stream: std::os::unix::net::UnixStream,
+    path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixConnection {
+    ///Wrap connected stream, fails if it's disconnected or peer socket has no path
+    pub fn new(stream: std::os::unix::net::UnixStream) -> io::Result<Self> {
+        let path = stream.peer_addr()?
+            .as_pathname()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Unnamed socket can't be reconnected"))?
+            .to_path_buf();
+
+        Ok(UnixConnection { stream, path })
+    }
+
+    pub fn connect<P: AsRef<std::path::Path>>(path: P) -> io::Result<Self> {
+        Ok(UnixConnection {
+            stream: std::os::unix::net::UnixStream::connect(&path)?,
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Read for UnixConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+#[cfg(unix)]
+impl Write for UnixConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixConnection {
+    ///Connect to socket path stored when connection was created
+    fn reconnect(&mut self) -> io::Result<()> {
+        self.stream = std::os::unix::net::UnixStream::connect(&self.path)?;
+
+        Ok(())
+    }
+
+    fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.stream.read_timeout()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.stream.write_timeout()
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_write_timeout(timeout)
+    }
+
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(UnixConnection { stream: self.stream.try_clone()?, path: self.path.clone() }))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        self.stream.shutdown(Shutdown::Both)
+    }
+}
+
+///Boxed connection of client, opaque in `Debug` output
+pub(crate) struct Transport(Box<dyn Connection>);
+
+impl Transport {
+    pub(crate) fn new<C: Connection + 'static>(connection: C) -> Self {
+        Transport(Box::new(connection))
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Transport> {
+        Ok(Transport(self.0.try_clone()?))
+    }
+}
+
+impl fmt::Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Connection")
+    }
+}
+
+impl Deref for Transport {
+    type Target = dyn Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl DerefMut for Transport {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0.as_mut()
+    }
+}
+
+impl Read for Transport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Transport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        io::{self, Cursor, Read, Write},
+        sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::Connection;
+    use crate::{Client, Values};
+
+    ///Transport replaying scripted replies, every reconnect starts next script without timeouts like new socket
+    struct ScriptedConnection {
+        scripts: VecDeque<&'static [u8]>,
+        current: Cursor<&'static [u8]>,
+        reconnects: Arc<AtomicUsize>,
+        timeouts: Mutex<(Option<Duration>, Option<Duration>)>,
+    }
+
+    impl Read for ScriptedConnection {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.current.read(buf)
+        }
+    }
+
+    impl Write for ScriptedConnection {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Connection for ScriptedConnection {
+        fn reconnect(&mut self) -> io::Result<()> {
+            self.reconnects.fetch_add(1, Ordering::SeqCst);
+            let script = self.scripts.pop_front().ok_or_else(|| io::Error::from(io::ErrorKind::ConnectionRefused))?;
+            self.current = Cursor::new(script);
+            *self.timeouts.lock().unwrap() = (None, None);
+
+            Ok(())
+        }
+
+        fn read_timeout(&self) -> io::Result<Option<Duration>> {
+            Ok(self.timeouts.lock().unwrap().0)
+        }
+
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+            self.timeouts.lock().unwrap().0 = timeout;
+
+            Ok(())
+        }
+
+        fn write_timeout(&self) -> io::Result<Option<Duration>> {
+            Ok(self.timeouts.lock().unwrap().1)
+        }
+
+        fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+            self.timeouts.lock().unwrap().1 = timeout;
+
+            Ok(())
+        }
+    }
+
+    ///Transport accepting at most three bytes per write, first write is interrupted
+    struct ChunkedConnection {
+        written: Arc<Mutex<Vec<u8>>>,
+        interrupted: bool,
+        reply: Cursor<&'static [u8]>,
+    }
+
+    impl Read for ChunkedConnection {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reply.read(buf)
+        }
+    }
+
+    impl Write for ChunkedConnection {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if !self.interrupted {
+                self.interrupted = true;
+
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+
+            let len = buf.len().min(3);
+            self.written.lock().unwrap().extend_from_slice(&buf[..len]);
+
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Connection for ChunkedConnection {
+        fn reconnect(&mut self) -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+        }
+    }
+
+    #[test]
+    fn whole_command_written_in_chunks() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let connection = ChunkedConnection {
+            written: Arc::clone(&written),
+            interrupted: false,
+            reply: Cursor::new(b"+OK\r\n".as_ref()),
+        };
+        let mut client = Client::from_connection(connection);
+
+        client.set("key", "value").unwrap();
+        assert_eq!(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n".to_vec(), *written.lock().unwrap());
+    }
+
+    #[test]
+    fn lost_connection_reconnected_by_transport() {
+        let reconnects = Arc::new(AtomicUsize::new(0));
+        let connection = ScriptedConnection {
+            scripts: VecDeque::from(vec![b"+PONG\r\n".as_ref()]),
+            current: Cursor::new(b"".as_ref()),
+            reconnects: Arc::clone(&reconnects),
+            timeouts: Mutex::new((None, None)),
+        };
+        let mut client = Client::from_connection(connection);
+        client.set_auto_reconnect(true);
+
+        assert_eq!(Values::SimpleString(String::from("PONG")), client.command(crate::Cmd::cmd("PING")).unwrap());
+        assert_eq!(1, reconnects.load(Ordering::SeqCst));
+        assert!(client.command(crate::Cmd::cmd("PING")).is_err());
+        assert_eq!(2, reconnects.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn timeouts_kept_after_reconnect() {
+        let connection = ScriptedConnection {
+            scripts: VecDeque::from(vec![b"+PONG\r\n".as_ref()]),
+            current: Cursor::new(b"".as_ref()),
+            reconnects: Arc::new(AtomicUsize::new(0)),
+            timeouts: Mutex::new((None, None)),
+        };
+        let mut client = Client::from_connection(connection);
+        client.set_auto_reconnect(true);
+        client.connect.get_ref().set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        client.connect.get_ref().set_write_timeout(Some(Duration::from_secs(2))).unwrap();
+
+        client.ping().unwrap();
+        assert_eq!(Some(Duration::from_secs(1)), client.connect.get_ref().read_timeout().unwrap());
+        assert_eq!(Some(Duration::from_secs(2)), client.connect.get_ref().write_timeout().unwrap());
+    }
+
+    #[test]
+    fn tcp_timeouts_kept_after_reconnect() {
+        let server = crate::mock::MockServer::sessions(&[&[], &["+PONG\r\n"]]);
+        let mut client = server.client();
+        client.connect.get_ref().set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        client.connect.get_ref().set_write_timeout(Some(Duration::from_secs(2))).unwrap();
+
+        client.reconnect().unwrap();
+        client.ping().unwrap();
+        assert_eq!(Some(Duration::from_secs(1)), client.connect.get_ref().read_timeout().unwrap());
+        assert_eq!(Some(Duration::from_secs(2)), client.connect.get_ref().write_timeout().unwrap());
+    }
+
+    #[test]
+    fn reset_connection_reconnected_to_stored_address() {
+        use std::{io::BufReader, net::TcpListener, thread};
+
+        use super::TcpConnection;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            //Closing socket with unread data resets connection
+            let (reset, _) = listener.accept().unwrap();
+            reset.peek(&mut [0]).unwrap();
+            drop(reset);
+
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            crate::parse_response(&mut reader).unwrap();
+            reader.get_mut().write_all(b"+PONG\r\n").unwrap();
+        });
+        let mut client = Client::from_connection(TcpConnection::connect(addr).unwrap());
+        client.set_auto_reconnect(true);
+
+        assert_eq!(Values::SimpleString(String::from("PONG")), client.command(crate::Cmd::cmd("PING")).unwrap());
+        server.join().unwrap();
+    }
+}
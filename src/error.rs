@@ -0,0 +1,208 @@
+use std::{fmt, io, num::ParseIntError, string::FromUtf8Error};
+
+use crate::Values;
+
+///Max count of characters of reply kept by `RedisError::UnexpectedReply`
+const REPLY_SNIPPET_LEN: usize = 128;
+
+///Errors returned by redis client
+#[derive(Debug)]
+pub enum RedisError {
+    ///Error of underlying connection
+    Io(io::Error),
+    ///Response from server can't be parsed
+    Protocol(String),
+    ///Error reply returned by server, `code` is its first word like `ERR` or `NOSCRIPT`
+    Server { code: String, message: String },
+    ///Command arguments rejected before sending
+    InvalidArgument(String),
+    ///None of addresses accepted connection
+    Connect(Vec<(String, RedisError)>),
+    ///Reply has different type than command returns, `reply` is its debug output truncated to 128 characters
+    UnexpectedReply { expected: &'static str, got: &'static str, reply: String },
+    ///Reply has more elements than allowed
+    ReplyTooLarge { limit: usize, len: usize },
+    ///Required field is missing in hash reply
+    MissingField(String),
+    ///Check of test helper failed
+    AssertionFailed(String),
+    ///Connection is subscribed to channels and accepts only listed commands
+    InSubscriberMode { allowed: &'static [&'static str] },
+    ///Key used by command holds value of other type
+    WrongType { command: String, key: String },
+    ///Deadline passed before reply was received
+    TimedOut,
+    ///Transaction wasn't executed because watched key was changed
+    TransactionAborted,
+    ///Error of command at index in pipeline or transaction
+    AtIndex { index: usize, error: Box<RedisError> },
+    ///Parse of array reply failed after `partial` elements were parsed
+    PartialArray { error: Box<RedisError>, partial: Vec<Values> },
+    ///Stored value isn't valid JSON of requested type
+    #[cfg(feature = "serde")]
+    Deserialize(serde_json::Error),
+}
+
+///Reply byte breaking RESP framing, passed to protocol error handler
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolViolation {
+    ///Frame part parser expected, like `integer` or `type byte`
+    pub expected: &'static str,
+    ///Offset of offending byte from start of reply
+    pub offset: usize,
+    ///Short prefix of unread reply starting with offending byte
+    pub bytes: Vec<u8>,
+}
+
+///Kind of cluster redirection
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RedirectKind {
+    ///Slot moved to other node permanently
+    Moved,
+    ///Slot is being migrated, only next command should go to other node after `ASKING`
+    Ask,
+}
+
+///Cluster redirection from `-MOVED` or `-ASK` error reply
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Redirect {
+    pub kind: RedirectKind,
+    pub slot: u16,
+    ///Address of node serving slot, like `127.0.0.1:7001`
+    pub addr: String,
+}
+
+impl Redirect {
+    ///Parse code and message of error reply, `None` unless it's well formed redirection
+    pub(crate) fn parse(code: &str, message: &str) -> Option<Redirect> {
+        let kind = match code {
+            "MOVED" => RedirectKind::Moved,
+            "ASK" => RedirectKind::Ask,
+            _ => return None,
+        };
+
+        let (slot, addr) = message.split_once(' ')?;
+
+        Some(Redirect { kind, slot: slot.parse().ok()?, addr: addr.trim().to_string() })
+    }
+}
+
+///Split error reply into code and rest of message
+pub(crate) fn split_error(reply: &str) -> (&str, &str) {
+    match reply.find(char::is_whitespace) {
+        Some(end) => (&reply[..end], reply[end..].trim_start()),
+        None => (reply, ""),
+    }
+}
+
+impl RedisError {
+    ///Error for error reply of server
+    pub(crate) fn server(reply: String) -> Self {
+        let (code, message) = split_error(&reply);
+
+        RedisError::Server { code: code.to_string(), message: message.to_string() }
+    }
+
+    ///Error for reply of other shape than expected
+    pub(crate) fn unexpected_reply(expected: &'static str, value: &Values) -> Self {
+        let mut reply = format!("{:?}", value);
+        if let Some((end, _)) = reply.char_indices().nth(REPLY_SNIPPET_LEN) {
+            reply.truncate(end);
+            reply.push_str("...");
+        }
+
+        RedisError::UnexpectedReply { expected, got: value.kind(), reply }
+    }
+
+    ///Error of connection, also one which interrupted array reply
+    pub(crate) fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            RedisError::Io(e) => Some(e),
+            RedisError::PartialArray { error, .. } => error.io_error(),
+            _ => None,
+        }
+    }
+
+    ///Redirection of `-MOVED` or `-ASK` error reply
+    pub fn redirect(&self) -> Option<Redirect> {
+        match self {
+            RedisError::Server { code, message } => Redirect::parse(code, message),
+            _ => None,
+        }
+    }
+
+    ///Server is still loading dataset into memory
+    pub fn is_loading(&self) -> bool {
+        matches!(self, RedisError::Server { code, .. } if code == "LOADING")
+    }
+}
+
+impl fmt::Display for RedisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RedisError::Io(e) => write!(f, "io error: {}", e),
+            RedisError::Protocol(message) => write!(f, "protocol error: {}", message),
+            RedisError::Server { code, message } if message.is_empty() => write!(f, "server error: {}", code),
+            RedisError::Server { code, message } => write!(f, "server error: {} {}", code, message),
+            RedisError::InvalidArgument(message) => write!(f, "invalid argument: {}", message),
+            RedisError::UnexpectedReply { expected, got, reply } => {
+                write!(f, "expected {} reply, got {}: {}", expected, got, reply)
+            },
+            RedisError::ReplyTooLarge { limit, len } => write!(f, "reply has {} elements, limit is {}", len, limit),
+            RedisError::MissingField(field) => write!(f, "missing hash field {}", field),
+            RedisError::AssertionFailed(message) => write!(f, "assertion failed: {}", message),
+            RedisError::WrongType { command, key } => {
+                write!(f, "{} can't be used on key {}: it holds value of other type", command, key)
+            },
+            RedisError::TransactionAborted => write!(f, "transaction aborted, watched key was changed"),
+            RedisError::AtIndex { index, error } => write!(f, "command {}: {}", index, error),
+            RedisError::PartialArray { error, partial } => {
+                write!(f, "{} after {} array elements were parsed", error, partial.len())
+            },
+            RedisError::TimedOut => write!(f, "deadline passed before reply was received"),
+            #[cfg(feature = "serde")]
+            RedisError::Deserialize(e) => write!(f, "can't deserialize value: {}", e),
+            RedisError::InSubscriberMode { allowed } => {
+                write!(f, "connection is in subscriber mode, allowed commands: {}", allowed.join(", "))
+            },
+            RedisError::Connect(failures) => {
+                write!(f, "can't connect to any address")?;
+                for (addr, e) in failures {
+                    write!(f, "; {}: {}", addr, e)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for RedisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RedisError::Io(e) => Some(e),
+            RedisError::AtIndex { error, .. } | RedisError::PartialArray { error, .. } => Some(error.as_ref()),
+            #[cfg(feature = "serde")]
+            RedisError::Deserialize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RedisError {
+    fn from(e: io::Error) -> Self {
+        RedisError::Io(e)
+    }
+}
+
+impl From<ParseIntError> for RedisError {
+    fn from(e: ParseIntError) -> Self {
+        RedisError::Protocol(format!("invalid integer: {}", e))
+    }
+}
+
+impl From<FromUtf8Error> for RedisError {
+    fn from(e: FromUtf8Error) -> Self {
+        RedisError::Protocol(format!("invalid utf-8 string: {}", e))
+    }
+}
@@ -0,0 +1,27 @@
+use std::{fmt, io, net::SocketAddr};
+
+use crate::{error::ProtocolViolation, Values};
+
+///Boxed user callback, opaque in `Debug` output
+pub(crate) struct Hook<F: ?Sized>(pub(crate) Box<F>);
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+///Resolver of address string, replaces `ToSocketAddrs` in tests
+pub(crate) type Resolver = Hook<dyn Fn(&str) -> io::Result<Vec<SocketAddr>> + Send>;
+
+///Receiver of new encoding of watched key
+pub(crate) type EncodingObserver = Hook<dyn Fn(&str) + Send>;
+
+///Receiver of RESP3 push frames
+pub(crate) type PushHandler = Hook<dyn Fn(Values) + Send>;
+
+///Receiver of replies breaking RESP framing
+pub(crate) type ProtocolErrorHandler = Hook<dyn Fn(&ProtocolViolation) + Send>;
+
+///Receiver of client warnings
+pub(crate) type Logger = Hook<dyn Fn(&str) + Send>;
@@ -1,259 +1,1759 @@
-use std::{io::{BufRead, Cursor, Seek, SeekFrom, ErrorKind, Read, Write}, net::{TcpStream, ToSocketAddrs}};
+//!Simple implementation for redis client by tcp stream
 
-///Simple implementation for redis client by tcp stream
+use std::{collections::HashMap, convert::TryFrom, io::{BufRead, BufReader, ErrorKind, Read, Write}, net::{SocketAddr, TcpStream, ToSocketAddrs}, sync::Arc, time::{Duration, Instant}};
+
+mod builder;
+mod clock;
+mod commands;
+mod connection;
+mod error;
+mod hooks;
+mod pipeline;
+mod pool;
+mod pubsub;
+mod sentinel;
+#[cfg(feature = "testing")]
+mod testing;
+mod transaction;
+mod types;
+#[cfg(test)]
+mod mock;
+
+pub use builder::ClientBuilder;
+pub use commands::{
+    to_redis_score_arg, Aggregate, BitOp, BitUnit, Capabilities, ClientKillFilters, ClientType, Encoding,
+    ExpireOptions, FailoverOptions, GetExExpiry, LatencyStats, RateLimitResult, RedisType, SetOptions, ShutdownMode,
+    Timings, TrimStrategy, ZAddOptions,
+};
+#[cfg(unix)]
+pub use connection::UnixConnection;
+pub use connection::{Connection, TcpConnection};
+pub use error::{ProtocolViolation, Redirect, RedirectKind, RedisError};
+pub use pipeline::Pipeline;
+pub use pool::{Pool, PoolStats, PooledClient};
+pub use pubsub::{KeyspaceEvent, OtherChannels, PubSubMessage, Subscriber};
+#[cfg(feature = "testing")]
+pub use testing::{expect_bulk, expect_int, expect_ok};
+pub use transaction::Transaction;
+pub use types::{BigInt, FromRedisValue, HashFields};
 
 const BULK_STRING_BYTE: u8 = 36;
 const INTEGER_BYTE: u8 = 58;
 const SIMPLE_STRING_BYTE: u8 = 43;
 const ERROR_STRING_BYTE: u8 = 45;
 const ARRAYS_BYTE: u8 = 42;
+const MAP_BYTE: u8 = 37;
+const NULL_BYTE: u8 = 95;
+const PUSH_BYTE: u8 = 62;
+const DOUBLE_BYTE: u8 = 44;
+const BIG_NUMBER_BYTE: u8 = 40;
+
+///Max count of keys returned by `Client::keys` unless changed
+const DEFAULT_KEYS_LIMIT: usize = 10_000;
+
+///Max count of channels sent in one subscription command unless changed
+const DEFAULT_SUBSCRIBE_CHUNK_SIZE: usize = 1_000;
+
+///Max count of nested arrays and maps in one reply unless changed
+const DEFAULT_MAX_NESTING_DEPTH: usize = 128;
+
+///Max count of elements or bytes allocated up front from reply header, longer replies grow while they're read
+const MAX_PREALLOCATED: usize = 1024;
+
+///Client for connect to redis by tcp or custom `Connection`
+#[derive(Debug)]
+pub struct Client {
+    connect: BufReader<connection::Transport>,
+    origin: Origin,
+    pubsub: pubsub::PubSubState,
+    logger: Option<hooks::Logger>,
+    keys_limit: usize,
+    ///Max count of channels of one subscription command
+    subscribe_chunk_size: usize,
+    ///Max count of nested arrays and maps in reply, deeper replies are rejected
+    max_nesting_depth: usize,
+    ///Prepended to keys of typed helpers
+    key_prefix: Option<String>,
+    ///Arities of commands by lowercase name, `None` while arity check is disabled
+    arities: Option<HashMap<String, Option<i64>>>,
+    ///Selected database, selected again after reconnect
+    db: i64,
+    credentials: Option<commands::Credentials>,
+    auto_reconnect: bool,
+    resolver: Option<hooks::Resolver>,
+    encoding_watches: Vec<commands::EncodingWatch>,
+    ///Recent `TYPE` replies, `None` while type cache is off
+    type_cache: Option<commands::TypeCache>,
+    push_handler: Option<hooks::PushHandler>,
+    protocol_error_handler: Option<hooks::ProtocolErrorHandler>,
+    capabilities: Option<commands::Capabilities>,
+    ///Protocol version negotiated by `HELLO`, replayed after reconnect
+    protocol: u8,
+    ///Redirection of last reply, cleared by next reply which isn't error
+    last_redirect: Option<Redirect>,
+    ///Commands written while buffered writes are on, sent by `flush` or before next read
+    write_buffer: Vec<u8>,
+    buffered_writes: bool,
+    ///Reply of timed out command may still arrive, connection is unusable until reconnect
+    out_of_sync: bool,
+    ///Raw bytes of reply being read, kept while `execute_into` runs
+    recording: Option<Vec<u8>>,
+    ///Start of `execute_timed`, moved to arrival of first reply byte by `read_reply`
+    first_byte_at: Option<Instant>,
+    clock: Arc<dyn clock::Clock>,
+}
+
+///Where client connection came from, used for reconnect
+#[derive(Debug)]
+enum Origin {
+    ///Address as given by user, resolved again on reconnect
+    Addr(String),
+    ///Addresses resolved once when client was created from address which isn't string
+    Resolved(Vec<SocketAddr>),
+    Sentinels { sentinels: Vec<String>, master_name: String },
+    ///Transport passed by user, reconnected by `Connection::reconnect`
+    Connection,
+}
+
+impl Client {
+    ///Create redis client, reconnect uses addresses resolved now, see `connect_host` for following DNS changes
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379");
+    ///let mut client = Client::new(("redis.local", 6379));
+    ///```
+    pub fn new<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
+        let resolved: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let stream = TcpConnection::connect(&resolved[..])?;
+
+        Ok(Self::with_origin(stream, Origin::Resolved(resolved)))
+    }
+
+    ///Create redis client, host name is resolved again on every reconnect so failover behind DNS is followed
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::connect_host("redis.local:6379");
+    ///```
+    pub fn connect_host(addr: &str) -> std::io::Result<Self> {
+        let stream = TcpConnection::connect(addr)?;
+
+        Ok(Self::with_origin(stream, Origin::Addr(addr.to_string())))
+    }
+
+    ///Create redis client over custom transport, like Unix socket
+    ///```no_run
+    ///use redis_client::{Client, UnixConnection};
+    ///
+    ///let client = Client::from_connection(UnixConnection::connect("/run/redis.sock").unwrap());
+    ///```
+    pub fn from_connection<C: Connection + 'static>(connection: C) -> Self {
+        Self::with_origin(connection, Origin::Connection)
+    }
+
+    ///Connect to first reachable address, addresses are tried in order
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let client = Client::connect_any(&["10.0.0.1:6379", "10.0.0.2:6379"], Duration::from_secs(1));
+    ///```
+    pub fn connect_any(addrs: &[&str], per_addr_timeout: Duration) -> Result<Self, RedisError> {
+        let mut failures = Vec::new();
+
+        for addr in addrs {
+            match connect_timeout(addr, per_addr_timeout) {
+                Ok(stream) => return Ok(Self::with_origin(TcpConnection::new(stream)?, Origin::Addr(addr.to_string()))),
+                Err(e) => failures.push((addr.to_string(), e.into())),
+            }
+        }
+
+        Err(RedisError::Connect(failures))
+    }
+
+    ///Drop current connection and connect again, sentinels are queried again for master address
+    ///
+    ///Authentication, selected database and timeouts are restored on new connection
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.reconnect().unwrap();
+    ///```
+    pub fn reconnect(&mut self) -> Result<(), RedisError> {
+        //Timeouts of lost connection are set on new one
+        let read_timeout = self.connect.get_ref().read_timeout().unwrap_or(None);
+        let write_timeout = self.connect.get_ref().write_timeout().unwrap_or(None);
+
+        match &self.origin {
+            Origin::Addr(addr) => {
+                let resolved = match &self.resolver {
+                    Some(resolver) => (resolver.0)(addr)?,
+                    None => addr.to_socket_addrs()?.collect(),
+                };
+
+                self.connect = BufReader::new(connection::Transport::new(TcpConnection::connect(&resolved[..])?));
+            },
+            Origin::Resolved(resolved) => {
+                self.connect = BufReader::new(connection::Transport::new(TcpConnection::connect(&resolved[..])?));
+            },
+            Origin::Sentinels { sentinels, master_name } => {
+                let stream = TcpConnection::new(sentinel::connect_master(sentinels, master_name)?)?;
+                self.connect = BufReader::new(connection::Transport::new(stream));
+            },
+            Origin::Connection => {
+                self.connect.get_mut().reconnect()?;
+                let stale = self.connect.buffer().len();
+                self.connect.consume(stale);
+            },
+        }
+        self.connect.get_ref().set_read_timeout(read_timeout)?;
+        self.connect.get_ref().set_write_timeout(write_timeout)?;
+        self.out_of_sync = false;
+        //Selected database and protocol are replayed on new connection
+        let (db, protocol) = (self.db, self.protocol);
+        self.reset_state();
+        self.db = db;
+        self.protocol = protocol;
+
+        self.restore_session()
+    }
+
+    ///Forget state of current connection without sending anything to server
+    ///
+    ///Subscriptions, selected database, protocol, capabilities of `warm_up`, last redirect, cached types and arities
+    ///go back to state of new connection. Settings like credentials, timeouts and handlers are kept
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.reset_state();
+    ///```
+    pub fn reset_state(&mut self) {
+        self.pubsub = pubsub::PubSubState::default();
+        self.db = 0;
+        self.protocol = 2;
+        self.capabilities = None;
+        self.last_redirect = None;
+        if let Some(cache) = &mut self.type_cache {
+            cache.clear();
+        }
+        if let Some(arities) = &mut self.arities {
+            arities.clear();
+        }
+    }
+
+    ///Execute command
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let value = client.command(Cmd::cmd("GET").arg("key"));
+    ///```
+    pub fn command(&mut self, cmd: Cmd) -> Result<Values, RedisError> {
+        cmd.execute(self)
+    }
+
+    ///Write command without waiting for reply, read replies later by `recv_n`
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.send(Cmd::cmd("INCR").arg("counter")).unwrap();
+    ///client.send(Cmd::cmd("INCR").arg("counter")).unwrap();
+    ///let replies = client.recv_n(2).unwrap();
+    ///```
+    pub fn send(&mut self, cmd: Cmd) -> Result<(), RedisError> {
+        cmd.validate(self)?;
+        let bytes = cmd.encode(self);
+
+        Ok(self.write(&bytes)?)
+    }
+
+    ///Execute command reading raw reply into `buf`, buffer is cleared first and keeps raw reply after call
+    ///
+    ///Hot loops passing same buffer avoid allocating read buffer per command, parsed strings are still owned
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let mut buf = Vec::with_capacity(512);
+    ///let cmd = Cmd::cmd("GET").arg("key");
+    ///for _ in 0..1000 {
+    ///    let value = client.execute_into(&cmd, &mut buf).unwrap();
+    ///}
+    ///```
+    pub fn execute_into(&mut self, cmd: &Cmd, buf: &mut Vec<u8>) -> Result<Values, RedisError> {
+        buf.clear();
+        self.recording = Some(std::mem::take(buf));
+        let reply = cmd.execute_ref(self, None);
+        *buf = self.recording.take().unwrap_or_default();
+
+        reply
+    }
+
+    ///Keep written commands in buffer until `flush` or until reply is read, so many `send`s go out in one write
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_buffered_writes(true);
+    ///for i in 0..100 {
+    ///    client.send(Cmd::cmd("RPUSH").arg("events").arg(&i.to_string())).unwrap();
+    ///}
+    ///client.flush().unwrap();
+    ///```
+    pub fn set_buffered_writes(&mut self, on: bool) {
+        self.buffered_writes = on;
+    }
+
+    ///Send buffered commands
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if self.write_buffer.is_empty() {
+            return Ok(());
+        }
+
+        let buffer = std::mem::take(&mut self.write_buffer);
+        self.connect.get_mut().write_all(&buffer)
+    }
+
+    ///Read exactly `n` replies of commands written by `send`
+    pub fn recv_n(&mut self, n: usize) -> Result<Vec<Values>, RedisError> {
+        (0..n).map(|_| self.read_reply()).collect()
+    }
+
+    ///Run blocking command with read timeout raised by `extra`, previous timeout is restored afterwards even on panic
+    ///
+    ///Connection without read timeout is left without it
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let reply = client.with_blocking_timeout(Duration::from_secs(5), |client| {
+    ///    client.command(Cmd::cmd("BLPOP").arg("queue").arg("5"))
+    ///});
+    ///```
+    pub fn with_blocking_timeout<T>(&mut self, extra: Duration, f: impl FnOnce(&mut Self) -> T) -> T {
+        let _guard = self.connect.get_ref().try_clone().ok().and_then(|stream| {
+            let previous = stream.read_timeout().ok()??;
+            //Timeout too long to be represented blocks forever
+            stream.set_read_timeout(previous.checked_add(extra)).ok()?;
+
+            Some(TimeoutGuard { stream, previous })
+        });
+
+        f(self)
+    }
+
+    ///Read and discard replies already sent by server until none arrives within `timeout`, returns count of discarded replies
+    ///
+    ///Restores connection left out of sync after timeout mid-read without reconnecting
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let stale = client.drain(Duration::from_millis(10));
+    ///```
+    pub fn drain(&mut self, timeout: Duration) -> usize {
+        let previous = match self.connect.get_ref().read_timeout() {
+            Ok(previous) => previous,
+            Err(_) => return 0,
+        };
+        let deadline = self.clock.now() + timeout;
+        let mut drained = 0;
+
+        while let Some(left) = deadline.checked_duration_since(self.clock.now()).filter(|left| *left > Duration::from_secs(0)) {
+            if self.connect.get_ref().set_read_timeout(Some(left)).is_err() || self.read_reply().is_err() {
+                break;
+            }
+            drained += 1;
+        }
+        let _ = self.connect.get_ref().set_read_timeout(previous);
+
+        drained
+    }
+
+    ///Prefix keys of typed helpers like `get`, `set` and `hset` for multi-tenant keyspace
+    ///
+    ///Arguments of raw `Cmd` are sent as is, unless added by `Cmd::key`
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap().with_prefix(String::from("app:"));
+    ///
+    ///let value = client.get("x");
+    ///```
+    pub fn with_prefix(mut self, prefix: String) -> Self {
+        self.key_prefix = Some(prefix);
+
+        self
+    }
+
+    ///Set hook receiving warnings of client
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_logger(|message| eprintln!("redis: {}", message));
+    ///```
+    pub fn set_logger<F: Fn(&str) + Send + 'static>(&mut self, logger: F) {
+        self.logger = Some(hooks::Hook(Box::new(logger)));
+    }
+
+    fn warn(&self, message: &str) {
+        if let Some(logger) = &self.logger {
+            (logger.0)(message);
+        }
+    }
+
+    fn with_origin<C: Connection + 'static>(stream: C, origin: Origin) -> Self {
+        Client {
+            connect: BufReader::new(connection::Transport::new(stream)),
+            origin,
+            pubsub: pubsub::PubSubState::default(),
+            logger: None,
+            keys_limit: DEFAULT_KEYS_LIMIT,
+            subscribe_chunk_size: DEFAULT_SUBSCRIBE_CHUNK_SIZE,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            key_prefix: None,
+            arities: None,
+            db: 0,
+            credentials: None,
+            auto_reconnect: false,
+            resolver: None,
+            encoding_watches: Vec::new(),
+            type_cache: None,
+            push_handler: None,
+            protocol_error_handler: None,
+            capabilities: None,
+            last_redirect: None,
+            protocol: 2,
+            write_buffer: Vec::new(),
+            buffered_writes: false,
+            out_of_sync: false,
+            recording: None,
+            first_byte_at: None,
+            clock: clock::system(),
+        }
+    }
+
+    ///Execute command, with auto reconnect it's sent again after lost connection
+    ///when it wasn't written or it's `read_only` so executing it twice is harmless
+    fn execute(&mut self, bytes: Vec<u8>, read_only: bool) -> Result<Values, RedisError> {
+        if !self.auto_reconnect || self.pubsub.is_active() {
+            return self.execute_once(&bytes);
+        }
+
+        if let Err(e) = self.write(&bytes) {
+            if !is_disconnect(&e) {
+                return Err(e.into());
+            }
+            self.warn("Connection lost, reconnecting");
+            self.reconnect()?;
+
+            return self.execute_once(&bytes);
+        }
+
+        match self.read_reply() {
+            Err(e) if matches!(e.io_error(), Some(io) if is_disconnect(io)) => {
+                self.warn("Connection lost, reconnecting");
+                if read_only {
+                    self.reconnect()?;
+
+                    return self.execute_once(&bytes);
+                }
+
+                //Command may have been executed already, only connection is restored for next command
+                if self.reconnect().is_err() {
+                    self.warn("Reconnect failed");
+                }
+
+                Err(e)
+            },
+            result => result,
+        }
+    }
+
+    fn execute_once(&mut self, bytes: &[u8]) -> Result<Values, RedisError> {
+        self.write(bytes)?;
+
+        self.read_reply()
+    }
+
+    ///Execute command with read timeout set to time left until deadline
+    fn execute_deadline(&mut self, bytes: Vec<u8>, read_only: bool, deadline: Instant) -> Result<Values, RedisError> {
+        let left = match deadline.checked_duration_since(self.clock.now()) {
+            Some(left) if left > Duration::from_secs(0) => left,
+            _ => return Err(RedisError::TimedOut),
+        };
+
+        let previous = self.connect.get_ref().read_timeout()?;
+        self.connect.get_ref().set_read_timeout(Some(left))?;
+        let reply = self.execute(bytes, read_only);
+        //Reply already read is returned even if timeout can't be restored
+        let _ = self.connect.get_ref().set_read_timeout(previous);
+
+        match reply {
+            Err(ref e) if matches!(e.io_error(), Some(io) if !is_disconnect(io)) => {
+                self.abandon_reply();
+
+                Err(RedisError::TimedOut)
+            },
+            reply => reply,
+        }
+    }
+
+    ///Read one of several replies requested at once, after timeout connection is abandoned
+    ///so rest of them isn't taken as replies of next commands
+    pub(crate) fn read_pending_reply(&mut self) -> Result<Values, RedisError> {
+        match self.read_reply() {
+            Err(e) if matches!(e.io_error(), Some(io) if !is_disconnect(io)) => {
+                self.abandon_reply();
+
+                Err(e)
+            },
+            reply => reply,
+        }
+    }
+
+    ///Reply stopped being read mid-way by read timeout would be taken as reply of next command,
+    ///so connection is replaced, if it can't be reconnected now next write fails until `reconnect`
+    pub(crate) fn abandon_reply(&mut self) {
+        if self.reconnect().is_err() {
+            self.out_of_sync = true;
+        }
+    }
+
+    ///Every written frame goes here, `write_all` retries partial and interrupted writes of transport
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        if self.out_of_sync {
+            return Err(std::io::Error::new(
+                ErrorKind::NotConnected,
+                "Connection lost sync with server after timeout, reconnect it",
+            ));
+        }
+        if self.buffered_writes {
+            self.write_buffer.extend_from_slice(bytes);
+
+            return Ok(());
+        }
+
+        self.flush()?;
+        self.connect.get_mut().write_all(bytes)
+    }
+
+    ///Receive RESP3 push frames arriving before command replies, like invalidations of client tracking
+    ///
+    ///Without handler push frames are dropped
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_push_handler(|push| println!("push {:?}", push));
+    ///```
+    pub fn set_push_handler<F: Fn(Values) + Send + 'static>(&mut self, handler: F) {
+        self.push_handler = Some(hooks::Hook(Box::new(handler)));
+    }
+
+    ///Inspect replies breaking RESP framing, called with offending bytes before `RedisError::Protocol` is returned
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_protocol_error_handler(|violation| {
+    ///    eprintln!("expected {} at {}: {:?}", violation.expected, violation.offset, violation.bytes)
+    ///});
+    ///```
+    pub fn set_protocol_error_handler<F: Fn(&ProtocolViolation) + Send + 'static>(&mut self, handler: F) {
+        self.protocol_error_handler = Some(hooks::Hook(Box::new(handler)));
+    }
+
+    ///Read reply of command, push frames in front of it are passed to push handler
+    fn read_reply(&mut self) -> Result<Values, RedisError> {
+        self.flush()?;
+        if self.first_byte_at.is_some() {
+            self.connect.fill_buf()?;
+            self.first_byte_at = Some(self.clock.now());
+        }
+        self.dispatch_pushes()?;
+        if let Some(buf) = &mut self.recording {
+            buf.clear();
+        }
+
+        let reply = self.read_frame()?;
+        match &reply {
+            Values::Errors(e) => {
+                let (code, message) = error::split_error(e);
+                if let Some(redirect) = Redirect::parse(code, message) {
+                    self.last_redirect = Some(redirect);
+                }
+            },
+            _ => self.last_redirect = None,
+        }
+
+        Ok(reply)
+    }
+
+    ///Most recent `-MOVED` or `-ASK` redirection, cleared by next successful reply
+    ///
+    ///Shows slot migration while cluster is resharded
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:7000").unwrap();
+    ///
+    ///let _ = client.get("user:1");
+    ///if let Some(redirect) = client.last_redirect() {
+    ///    println!("slot {} is served by {}", redirect.slot, redirect.addr);
+    ///}
+    ///```
+    pub fn last_redirect(&self) -> Option<Redirect> {
+        self.last_redirect.clone()
+    }
+
+    ///Pass push frames waiting in front of next reply to push handler
+    fn dispatch_pushes(&mut self) -> Result<(), RedisError> {
+        while self.connect.fill_buf()?.first() == Some(&PUSH_BYTE) {
+            let push = self.read_frame()?;
+            match &self.push_handler {
+                Some(handler) => (handler.0)(push),
+                None => self.warn("Push frame dropped, no push handler is set"),
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Set max count of nested arrays and maps in one reply, deeper reply fails with `RedisError::Protocol`, default is 128
+    ///
+    ///Guards recursive parser against stack overflow on malicious replies
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_max_nesting_depth(16);
+    ///```
+    pub fn set_max_nesting_depth(&mut self, depth: usize) {
+        self.max_nesting_depth = depth;
+    }
+
+    ///Read any frame including push frames, bytes of frame are copied into recording buffer if it's set
+    fn read_frame(&mut self) -> Result<Values, RedisError> {
+        self.flush()?;
+
+        let depth = self.max_nesting_depth;
+        let mut recording = self.recording.take();
+        let mut tracker = Tracker { inner: &mut self.connect, consumed: 0 };
+        let result = match recording.as_mut() {
+            Some(buf) => parse_value(&mut Recorder { inner: &mut tracker, buf }, depth),
+            None => parse_value(&mut tracker, depth),
+        };
+        let offset = tracker.consumed;
+        self.recording = recording;
+
+        match (result, &self.protocol_error_handler) {
+            (Err(ParseError::Violation { expected, message }), Some(handler)) => {
+                let buffered = self.connect.buffer();
+                let bytes = buffered[..buffered.len().min(VIOLATION_PREFIX)].to_vec();
+                (handler.0)(&ProtocolViolation { expected, offset, bytes });
+
+                Err(RedisError::Protocol(message))
+            },
+            (result, _) => Ok(result?),
+        }
+    }
+}
+
+///Error means connection is lost, not just slow
+pub(crate) fn is_disconnect(e: &std::io::Error) -> bool {
+    !matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+}
+
+///Restores read timeout of connection when dropped
+struct TimeoutGuard {
+    stream: connection::Transport,
+    previous: Duration,
+}
+
+impl Drop for TimeoutGuard {
+    fn drop(&mut self) {
+        let _ = self.stream.set_read_timeout(Some(self.previous));
+    }
+}
+
+fn connect_timeout(addr: &str, timeout: Duration) -> std::io::Result<TcpStream> {
+    let mut last_error = std::io::Error::new(ErrorKind::InvalidInput, "Address resolved to nothing");
+
+    for socket_addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+///Struct for create redis command
+#[derive(Debug)]
+pub struct Cmd {
+    args: Vec<Vec<u8>>,
+    ///Positions of arguments which are keys
+    keys: Vec<usize>,
+    expect_resp3: bool,
+    ///Key deciding cluster node, set by `with_routing_key`
+    routing_key: Option<String>,
+}
+
+impl Cmd {
+    ///Start create command
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("PING");
+    ///```
+    #[allow(clippy::self_named_constructors)]
+    pub fn cmd(cmd: &str) -> Self {
+        let vec = vec![cmd.as_bytes().to_vec()];
+        Cmd { args: vec, keys: Vec::new(), expect_resp3: false, routing_key: None }
+    }
+
+    ///Set arguments to your command
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("SET").arg("key").arg("value");
+    ///```
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.as_bytes().to_vec());
+
+        self
+    }
+
+    ///Set key argument, it gets prefix of client set by `Client::with_prefix`
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("GET").key("key");
+    ///```
+    pub fn key(mut self, key: &str) -> Self {
+        self.keys.push(self.args.len());
+
+        self.arg(key)
+    }
+
+    ///Set binary argument, for values which aren't UTF-8
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("SET").arg("key").arg_bytes(&[0xff, 0x00]);
+    ///```
+    pub fn arg_bytes(mut self, arg: &[u8]) -> Self {
+        self.args.push(arg.to_vec());
+
+        self
+    }
+
+    ///Set field value pairs as flat arguments
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("HSET").key("user:1").arg_pairs(vec![("name", "Bob"), ("age", "42")]);
+    ///```
+    pub fn arg_pairs<I, K, V>(self, pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        pairs.into_iter().fold(self, |cmd, (field, value)| cmd.arg(field.as_ref()).arg(value.as_ref()))
+    }
+
+    ///Mark command as returning RESP3 map, flat RESP2 array reply is decoded to `Values::Map` too
+    ///```
+    ///use redis_client::Cmd;
+    ///
+    ///let cmd = Cmd::cmd("CONFIG").arg("GET").arg("max*").expect_resp3();
+    ///```
+    pub fn expect_resp3(mut self) -> Self {
+        self.expect_resp3 = true;
+
+        self
+    }
+
+    ///Set key deciding cluster node of command, for commands whose routing key isn't first key argument
+    ///
+    ///It isn't sent to server, standalone connection ignores it
+    ///```
+    ///use redis_client::{crc16_slot, Cmd};
+    ///
+    ///let cmd = Cmd::cmd("EVAL").arg("return 1").arg("0").with_routing_key("user:{42}");
+    ///let slot = cmd.routing_key().map(crc16_slot);
+    ///```
+    pub fn with_routing_key(mut self, key: &str) -> Self {
+        self.routing_key = Some(key.to_string());
+
+        self
+    }
+
+    ///Key deciding cluster node, one set by `with_routing_key` or else first key argument
+    pub fn routing_key(&self) -> Option<&str> {
+        match &self.routing_key {
+            Some(key) => Some(key),
+            None => self.keys.first().and_then(|&position| std::str::from_utf8(&self.args[position]).ok()),
+        }
+    }
+
+    fn args(self, args: &[&str]) -> Self {
+        args.iter().fold(self, |cmd, arg| cmd.arg(arg))
+    }
+
+    ///Execute command
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let cmd = Cmd::cmd("SET")
+    ///    .arg("key")
+    ///    .arg("value")
+    ///    .execute(&mut client);
+    ///```
+    pub fn execute(self, conn: &mut Client) -> Result<Values, RedisError> {
+        self.execute_ref(conn, None)
+    }
+
+    ///Every command passes here, reply is read with read timeout till `deadline` if it's set
+    fn execute_ref(&self, conn: &mut Client, deadline: Option<Instant>) -> Result<Values, RedisError> {
+        self.validate(conn)?;
+
+        let read_only = commands::is_read_only(&String::from_utf8_lossy(&self.args[0]));
+        let bytes = self.encode(conn);
+        let reply = match deadline {
+            Some(deadline) => conn.execute_deadline(bytes, read_only, deadline)?,
+            None => conn.execute(bytes, read_only)?,
+        };
+        conn.observe_encodings(self);
+        if self.expect_resp3 {
+            reply.into_map()
+        } else {
+            Ok(reply)
+        }
+    }
+
+    ///Execute command of typed helper, error reply is returned as `RedisError`
+    pub(crate) fn run(self, conn: &mut Client) -> Result<Values, RedisError> {
+        let command = String::from_utf8_lossy(&self.args[0]).into_owned();
+        let key = self.keys.first().map(|&position| String::from_utf8_lossy(&self.args[position]).into_owned());
+
+        match self.execute(conn)? {
+            Values::Errors(e) if e.starts_with("WRONGTYPE") => {
+                Err(RedisError::WrongType { command, key: key.unwrap_or_default() })
+            },
+            Values::Errors(e) => Err(RedisError::server(e)),
+            value => Ok(value),
+        }
+    }
+
+    ///Execute command expecting array reply and convert each element, failing element is reported by `RedisError::AtIndex`
+    ///```no_run
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let scores: Vec<i64> = Cmd::cmd("LRANGE").key("scores").arg("0").arg("-1").query_vec(&mut client).unwrap();
+    ///```
+    pub fn query_vec<T: FromRedisValue>(self, conn: &mut Client) -> Result<Vec<T>, RedisError> {
+        match self.run(conn)? {
+            Values::Arrays(values) => values.into_iter()
+                .enumerate()
+                .map(|(index, value)| T::from_redis_value(value)
+                    .map_err(|error| RedisError::AtIndex { index, error: Box::new(error) }))
+                .collect(),
+            other => Err(RedisError::unexpected_reply("array", &other)),
+        }
+    }
+
+    ///Execute command, failing with `RedisError::TimedOut` if reply doesn't arrive before deadline
+    ///
+    ///Same deadline can be passed to several commands sharing one time budget
+    ///```no_run
+    ///use std::time::{Duration, Instant};
+    ///use redis_client::{Cmd, Client};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let deadline = Instant::now() + Duration::from_millis(50);
+    ///let first = Cmd::cmd("GET").arg("first").execute_deadline(&mut client, deadline);
+    ///let second = Cmd::cmd("GET").arg("second").execute_deadline(&mut client, deadline);
+    ///```
+    pub fn execute_deadline(self, conn: &mut Client, deadline: Instant) -> Result<Values, RedisError> {
+        self.execute_ref(conn, Some(deadline))
+    }
+
+    ///Check command is allowed in current mode of connection and has valid arity if check is enabled
+    fn validate(&self, conn: &mut Client) -> Result<(), RedisError> {
+        if conn.pubsub.is_active() && !pubsub::allowed_in_subscriber_mode(&String::from_utf8_lossy(&self.args[0])) {
+            return Err(RedisError::InSubscriberMode { allowed: pubsub::SUBSCRIBER_MODE_COMMANDS });
+        }
+
+        conn.check_arity(&self.args)?;
+        conn.forget_types(self);
+
+        Ok(())
+    }
+
+    fn has_key(&self, key: &str) -> bool {
+        self.keys.iter().any(|&position| self.args[position] == key.as_bytes())
+    }
+
+    ///Encode command with key prefix of connection
+    fn encode(&self, conn: &Client) -> Vec<u8> {
+        match &conn.key_prefix {
+            Some(prefix) if !self.keys.is_empty() => {
+                let mut args = self.args.clone();
+                for &position in &self.keys {
+                    args[position].splice(0..0, prefix.bytes());
+                }
+
+                Self::create_command(&args)
+            },
+            _ => Self::create_command(&self.args),
+        }
+    }
+
+    fn create_command(args: &[Vec<u8>]) -> Vec<u8> {
+        let mut result = format!("*{}\r\n", args.len()).into_bytes();
+
+        for arg in args {
+            result.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+            result.extend_from_slice(arg);
+            result.extend_from_slice(b"\r\n");
+        }
+
+        result
+    }
+}
+
+///Enum for represent redis responses
+#[derive(Debug, PartialEq)]
+pub enum Values {
+    SimpleString(String),
+    Errors(String),
+    Integers(i64),
+    BulkString(String),
+    Arrays(Vec<Values>),
+    ///Null bulk string or null array
+    Nil,
+    ///RESP3 map of key value pairs
+    Map(Vec<(Values, Values)>),
+    ///Bulk string which isn't valid UTF-8
+    BulkBytes(Vec<u8>),
+    ///RESP3 floating point number
+    Double(f64),
+    ///RESP3 integer out of range of `i64`, kept as decimal digits
+    BigNumber(String),
+}
+
+impl Values {
+    ///Reply is `+OK` status
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Values::SimpleString(s) if s == "OK")
+    }
+
+    ///Code of error reply, its first word like `WRONGTYPE` or `NOSCRIPT`
+    ///```
+    ///use redis_client::Values;
+    ///
+    ///let reply = Values::Errors(String::from("NOSCRIPT No matching script"));
+    ///assert_eq!(Some("NOSCRIPT"), reply.error_code());
+    ///```
+    pub fn error_code(&self) -> Option<&str> {
+        match self {
+            Values::Errors(e) => Some(error::split_error(e).0),
+            _ => None,
+        }
+    }
+
+    ///Message of error reply without its code
+    pub fn error_message(&self) -> Option<&str> {
+        match self {
+            Values::Errors(e) => Some(error::split_error(e).1),
+            _ => None,
+        }
+    }
+
+    ///Name of reply type
+    ///```
+    ///use redis_client::Values;
+    ///
+    ///assert_eq!("integer", Values::Integers(1).kind());
+    ///```
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Values::SimpleString(_) => "simple string",
+            Values::Errors(_) => "error",
+            Values::Integers(_) => "integer",
+            Values::BulkString(_) => "bulk string",
+            Values::Arrays(_) => "array",
+            Values::Nil => "nil",
+            Values::Map(_) => "map",
+            Values::BulkBytes(_) => "bulk string",
+            Values::Double(_) => "double",
+            Values::BigNumber(_) => "big number",
+        }
+    }
+
+    ///Estimate of memory taken by value, every element costs size of `Values` plus length of its string
+    ///```
+    ///use redis_client::Values;
+    ///
+    ///let value = Values::Arrays(vec![Values::BulkString(String::from("abc"))]);
+    ///assert_eq!(2 * std::mem::size_of::<Values>() + 3, value.byte_size());
+    ///```
+    pub fn byte_size(&self) -> usize {
+        let content = match self {
+            Values::SimpleString(s) | Values::Errors(s) | Values::BulkString(s) | Values::BigNumber(s) => s.len(),
+            Values::BulkBytes(bytes) => bytes.len(),
+            Values::Integers(_) | Values::Double(_) | Values::Nil => 0,
+            Values::Arrays(values) => values.iter().map(Values::byte_size).sum(),
+            Values::Map(pairs) => pairs.iter().map(|(key, value)| key.byte_size() + value.byte_size()).sum(),
+        };
+
+        std::mem::size_of::<Values>() + content
+    }
+
+    ///Turn flat array of key value pairs into map, other replies are kept
+    fn into_map(self) -> Result<Values, RedisError> {
+        match self {
+            Values::Arrays(values) => {
+                if values.len() % 2 != 0 {
+                    return Err(RedisError::Protocol(String::from("Map reply has odd number of elements")));
+                }
+
+                let mut pairs = Vec::with_capacity(values.len() / 2);
+                let mut values = values.into_iter();
+                while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                    pairs.push((key, value));
+                }
+
+                Ok(Values::Map(pairs))
+            },
+            value => Ok(value),
+        }
+    }
+}
+
+///Count of cluster hash slots
+const CLUSTER_SLOTS: u16 = 16384;
+
+///Cluster hash slot of key, only part inside first non empty `{...}` hash tag is hashed
+///```
+///use redis_client::crc16_slot;
+///
+///assert_eq!(crc16_slot("user:{42}:name"), crc16_slot("user:{42}:email"));
+///```
+pub fn crc16_slot(key: &str) -> u16 {
+    let key = key.as_bytes();
+    let hashed = match key.iter().position(|&b| b == b'{') {
+        Some(open) => match key[open + 1..].iter().position(|&b| b == b'}') {
+            Some(len) if len > 0 => &key[open + 1..open + 1 + len],
+            _ => key,
+        },
+        None => key,
+    };
+
+    //CRC16 XMODEM: polynomial 0x1021, initial value 0
+    let crc = hashed.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 }
+        })
+    });
+
+    crc % CLUSTER_SLOTS
+}
+
+///Parse one reply from start of buffer, returns it with count of consumed bytes, rest of buffer is left untouched
+///```
+///use redis_client::{parse_frame, Values};
+///
+///let (value, consumed) = parse_frame(b":1\r\n:2\r\n").unwrap();
+///assert_eq!(Values::Integers(1), value);
+///assert_eq!(4, consumed);
+///```
+pub fn parse_frame(buf: &[u8]) -> Result<(Values, usize), RedisError> {
+    let mut rest = buf;
+    let value = parse_response(&mut rest)?;
+
+    Ok((value, buf.len() - rest.len()))
+}
+
+///Parse buffer holding exactly one reply, trailing data other than whitespace or NUL padding is rejected
+///```
+///use redis_client::{parse_single_strict, Values};
+///
+///assert_eq!(Values::Integers(1), parse_single_strict(b":1\r\n\0\0").unwrap());
+///assert!(parse_single_strict(b":1\r\n:2\r\n").is_err());
+///```
+pub fn parse_single_strict(buf: &[u8]) -> Result<Values, RedisError> {
+    let (value, consumed) = parse_frame(buf)?;
+
+    let trailing = buf[consumed..].iter().filter(|byte| !matches!(byte, 0 | b' ' | b'\t' | b'\r' | b'\n')).count();
+    if trailing > 0 {
+        return Err(RedisError::Protocol(format!("Unexpected {} bytes of data after reply", trailing)));
+    }
+
+    Ok(value)
+}
+
+///Type bytes of frames known by parser
+const TYPE_BYTES: &[u8] = &[
+    INTEGER_BYTE, DOUBLE_BYTE, BIG_NUMBER_BYTE, BULK_STRING_BYTE, SIMPLE_STRING_BYTE, ERROR_STRING_BYTE, ARRAYS_BYTE,
+    PUSH_BYTE, NULL_BYTE, MAP_BYTE,
+];
+
+///How many bytes from offending one are kept in `ProtocolViolation`
+const VIOLATION_PREFIX: usize = 16;
+
+///Failure of parser, on framing violation offending byte is left unread
+enum ParseError {
+    Failed(RedisError),
+    Violation { expected: &'static str, message: String },
+}
+
+impl From<RedisError> for ParseError {
+    fn from(e: RedisError) -> Self {
+        ParseError::Failed(e)
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        ParseError::Failed(RedisError::Io(e))
+    }
+}
+
+impl From<ParseError> for RedisError {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::Failed(e) => e,
+            ParseError::Violation { message, .. } => RedisError::Protocol(message),
+        }
+    }
+}
+
+///Reader counting bytes consumed by parser, gives offset of protocol violation
+struct Tracker<'a, R> {
+    inner: &'a mut R,
+    consumed: usize,
+}
+
+impl<R: BufRead> std::io::Read for Tracker<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.consumed += read;
+
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Tracker<'_, R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.consumed += amt;
+        self.inner.consume(amt);
+    }
+}
+
+///Reader copying bytes consumed by parser into buffer
+struct Recorder<'a, R> {
+    inner: &'a mut R,
+    buf: &'a mut Vec<u8>,
+}
+
+impl<R: BufRead> std::io::Read for Recorder<'_, R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(out)?;
+        self.buf.extend_from_slice(&out[..read]);
+
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Recorder<'_, R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buffered) = self.inner.fill_buf() {
+            self.buf.extend_from_slice(&buffered[..amt.min(buffered.len())]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+///Function for parse one redis response from tcp stream
+fn parse_response<R: BufRead>(reader: &mut R) -> Result<Values, RedisError> {
+    Ok(parse_value(reader, DEFAULT_MAX_NESTING_DEPTH)?)
+}
+
+///Parse one value, `depth` is count of arrays and maps it may still be nested in
+fn parse_value<R: BufRead>(reader: &mut R, depth: usize) -> Result<Values, ParseError> {
+    let type_byte = match reader.fill_buf()?.first() {
+        Some(&byte) => byte,
+        None => return Err(RedisError::Io(std::io::Error::new(ErrorKind::UnexpectedEof, "Empty response")).into()),
+    };
+    if !TYPE_BYTES.contains(&type_byte) {
+        return Err(ParseError::Violation {
+            expected: "type byte",
+            message: format!("Unknown response type byte {}", type_byte),
+        });
+    }
+    reader.consume(1);
+
+    if depth == 0 && matches!(type_byte, ARRAYS_BYTE | PUSH_BYTE | MAP_BYTE) {
+        return Err(RedisError::Protocol(String::from("max nesting depth exceeded")).into());
+    }
+
+    match type_byte {
+        INTEGER_BYTE => Ok(Values::Integers(read_integer(reader)?)),
+        DOUBLE_BYTE => {
+            let line = read_line(reader)?;
+            let double = line.parse().map_err(|_| RedisError::Protocol(format!("invalid double: {}", line)))?;
+
+            Ok(Values::Double(double))
+        },
+        BIG_NUMBER_BYTE => Ok(Values::BigNumber(read_line(reader)?)),
+        BULK_STRING_BYTE => {
+            let size = read_integer(reader)?;
+            if size < 0 {
+                return Ok(Values::Nil);
+            }
+
+            let size = usize::try_from(size)
+                .map_err(|_| RedisError::Protocol(format!("Bulk string size {} doesn't fit in memory", size)))?;
+
+            //Size isn't trusted, buffer grows only with bytes which really arrived
+            let mut buff = Vec::with_capacity(size.min(MAX_PREALLOCATED));
+            reader.by_ref().take(size as u64).read_to_end(&mut buff)?;
+            if buff.len() < size {
+                return Err(std::io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of response").into());
+            }
+            skip_line_end(reader)?;
+
+            match String::from_utf8(buff) {
+                Ok(s) => Ok(Values::BulkString(s)),
+                Err(e) => Ok(Values::BulkBytes(e.into_bytes())),
+            }
+        },
+        SIMPLE_STRING_BYTE => Ok(Values::SimpleString(read_line(reader)?)),
+        ERROR_STRING_BYTE => Ok(Values::Errors(read_line(reader)?)),
+        ARRAYS_BYTE | PUSH_BYTE => {
+            let line_count = read_integer(reader)?;
+            if line_count < 0 {
+                return Ok(Values::Nil);
+            }
+
+            let mut v: Vec<Values> = Vec::with_capacity(line_count.min(MAX_PREALLOCATED as i64) as usize);
+            for _ in 0..line_count {
+                match parse_value(reader, depth - 1) {
+                    Ok(value) => v.push(value),
+                    Err(ParseError::Failed(error)) if !v.is_empty() => {
+                        return Err(RedisError::PartialArray { error: Box::new(error), partial: v }.into());
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(Values::Arrays(v))
+        },
+        NULL_BYTE => {
+            read_line(reader)?;
+
+            Ok(Values::Nil)
+        },
+        MAP_BYTE => {
+            let pairs_count = read_integer(reader)?;
+            if pairs_count < 0 {
+                return Err(RedisError::Protocol(format!("Negative map size {}", pairs_count)).into());
+            }
+
+            let mut pairs = Vec::with_capacity(pairs_count.min(MAX_PREALLOCATED as i64) as usize);
+            for _ in 0..pairs_count {
+                pairs.push((parse_value(reader, depth - 1)?, parse_value(reader, depth - 1)?));
+            }
+
+            Ok(Values::Map(pairs))
+        },
+        _ => unreachable!("type byte is checked before"),
+    }
+}
+
+///Read line without trailing CRLF
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, RedisError> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Err(RedisError::Io(std::io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of response")));
+    }
+
+    if line.ends_with(b"\n") {
+        line.pop();
+    }
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+
+    Ok(String::from_utf8(line)?)
+}
+
+///Read integer line in place from reader buffer without allocating, offending byte is left unread
+fn read_integer<R: BufRead>(reader: &mut R) -> Result<i64, ParseError> {
+    let mut value: i64 = 0;
+    let mut negative = false;
+    let mut digits = 0;
+    //CR is allowed only right before LF
+    let mut carriage = false;
+
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Err(std::io::Error::new(ErrorKind::UnexpectedEof, "Unexpected end of response").into());
+        }
+
+        let mut used = 0;
+        let mut line_end = false;
+        let mut invalid = None;
+        for &byte in buf {
+            match byte {
+                b'\n' => line_end = true,
+                byte if carriage => invalid = Some(format!("invalid integer: unexpected byte {} after CR", byte)),
+                b'\r' => carriage = true,
+                b'-' if digits == 0 && !negative => negative = true,
+                b'0'..=b'9' => {
+                    let digit = i64::from(byte - b'0');
+                    match value.checked_mul(10).and_then(|v| if negative { v.checked_sub(digit) } else { v.checked_add(digit) }) {
+                        Some(next) => value = next,
+                        None => invalid = Some(String::from("invalid integer: number too large")),
+                    }
+                    digits += 1;
+                },
+                byte => invalid = Some(format!("invalid integer: unexpected byte {}", byte)),
+            }
+
+            if invalid.is_some() {
+                break;
+            }
+            used += 1;
+            if line_end {
+                break;
+            }
+        }
+        reader.consume(used);
+
+        if let Some(message) = invalid {
+            return Err(ParseError::Violation { expected: "integer", message });
+        }
+        if line_end {
+            break;
+        }
+    }
+
+    if digits == 0 {
+        return Err(RedisError::Protocol(String::from("invalid integer: no digits")).into());
+    }
+
+    Ok(value)
+}
+
+///Skip CRLF after bulk string data, end of stream is allowed
+fn skip_line_end<R: BufRead>(reader: &mut R) -> Result<(), ParseError> {
+    for byte in b"\r\n" {
+        match reader.fill_buf()?.first() {
+            Some(b) if b == byte => reader.consume(1),
+            Some(_) => {
+                return Err(ParseError::Violation {
+                    expected: "CRLF",
+                    message: String::from("Bulk string length mismatch"),
+                })
+            },
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::Clock;
+    use crate::parse_response;
+    use crate::Values;
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn test_set_value() {
+        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+        let result = crate::Cmd::cmd("SET").arg("\ntest\n").arg("test\n").execute(&mut client);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn connect_any_skips_dead_address() {
+        let dead = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let server = crate::mock::MockServer::new(&["+OK\r\n"]);
+        let live = server.addr().to_string();
 
-///Client for connect to redis by tcp
-#[derive(Debug)]
-pub struct Client {
-    connect: TcpStream,
-}
+        let mut client = crate::Client::connect_any(&[&dead, &live], std::time::Duration::from_secs(1)).unwrap();
 
-impl Client {
-    ///Create redis client
-    ///```no_run
-    ///use redis_client::Client;
-    ///let mut client = Client::new("127.0.0.1:6379");
-    ///```
-    pub fn new<A: ToSocketAddrs>(addr: A) -> std::io::Result<Self> {
-        let connect = TcpStream::connect(addr)?;
+        assert_eq!(
+            Values::SimpleString(String::from("OK")),
+            crate::Cmd::cmd("SET").arg("key").arg("value").execute(&mut client).unwrap()
+        );
+    }
+
+    #[test]
+    fn connect_any_lists_all_failures() {
+        let dead = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
 
-        Ok(Client {connect})
+        match crate::Client::connect_any(&[&dead, "not an address"], std::time::Duration::from_secs(1)) {
+            Err(crate::RedisError::Connect(failures)) => {
+                assert_eq!(vec![dead.as_str(), "not an address"], failures.iter().map(|(addr, _)| addr.as_str()).collect::<Vec<_>>());
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
-    fn execute(&mut self, bytes: Vec<u8>) -> std::io::Result<Vec<u8>> {
-        self.connect.write_all(&bytes)?;
-        let mut buff: Vec<u8> = vec![0; 1024];
+    #[test]
+    fn elapsed_deadline_times_out_without_writing() {
+        let server = crate::mock::MockServer::new(&["+OK\r\n"]);
+        let mut client = server.client();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
 
-        self.connect.read(&mut buff)?;
+        let result = crate::Cmd::cmd("GET").arg("key").execute_deadline(&mut client, clock.now());
 
-        Ok(buff)
+        assert!(matches!(result, Err(crate::RedisError::TimedOut)));
+        drop(client);
+        assert!(server.commands().is_empty());
     }
-}
 
-///Struct for create redis command
-#[derive(Debug)]
-pub struct Cmd {
-    args: Vec<String>,
-}
+    #[test]
+    fn deadline_shared_by_commands() {
+        let server = crate::mock::MockServer::new(&["+OK\r\n", ":1\r\n"]);
+        let mut client = server.client();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+        let deadline = clock.now() + std::time::Duration::from_secs(5);
 
-impl Cmd {
-    ///Start create command
-    ///```
-    ///use redis_client::Cmd;
-    ///
-    ///let cmd = Cmd::cmd("PING");
-    ///```
-    pub fn cmd(cmd: &str) -> Self {
-        let vec = vec![cmd.to_string()];
-        Cmd { args: vec }
+        assert_eq!(Values::SimpleString("OK".to_string()), crate::Cmd::cmd("SET").arg("key").arg("1").execute_deadline(&mut client, deadline).unwrap());
+        assert_eq!(Values::Integers(1), crate::Cmd::cmd("EXISTS").arg("key").execute_deadline(&mut client, deadline).unwrap());
     }
 
-    ///Set arguments to your command
-    ///```
-    ///use redis_client::Cmd;
-    ///
-    ///let cmd = Cmd::cmd("SET").arg("key").arg("value");
-    ///```
-    pub fn arg(mut self, arg: &str) -> Self {
-        self.args.push(arg.to_string());
+    #[test]
+    fn prefix_keys_of_typed_helpers() {
+        let server = crate::mock::MockServer::new(&["$1\r\n1\r\n", "+OK\r\n", "+OK\r\n"]);
+        let mut client = server.client().with_prefix(String::from("app:"));
 
-        self
+        assert_eq!(Some(String::from("1")), client.get("x").unwrap());
+        client.mset(&[("a", "1"), ("b", "2")]).unwrap();
+        client.command(crate::Cmd::cmd("SET").arg("raw").arg("1")).unwrap();
+        drop(client);
+        assert_eq!(
+            vec![vec!["GET", "app:x"], vec!["MSET", "app:a", "1", "app:b", "2"], vec!["SET", "raw", "1"]],
+            server.commands()
+        );
     }
 
-    ///Execute command
-    ///```no_run
-    ///use redis_client::{Cmd, Client};
-    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
-    ///
-    ///let cmd = Cmd::cmd("SET")
-    ///    .arg("key")
-    ///    .arg("value")
-    ///    .execute(&mut client);
-    ///```
-    pub fn execute(self, conn: &mut Client) -> std::io::Result<Values> {
-        if let Ok (result) = parse_response(&mut conn.execute(Self::create_command(&self.args))?) {
-            Ok(result)
-        } else {
-            Err(std::io::Error::new(ErrorKind::Other, "Error"))
-        }
+    #[test]
+    fn send_then_recv_n() {
+        let server = crate::mock::MockServer::new(&["+OK\r\n", "$1\r\n1\r\n", ":2\r\n"]);
+        let mut client = server.client();
+
+        client.send(crate::Cmd::cmd("SET").arg("key").arg("1")).unwrap();
+        client.send(crate::Cmd::cmd("GET").arg("key")).unwrap();
+        client.send(crate::Cmd::cmd("INCR").arg("key")).unwrap();
+
+        assert_eq!(
+            vec![Values::SimpleString(String::from("OK")), Values::BulkString(String::from("1")), Values::Integers(2)],
+            client.recv_n(3).unwrap()
+        );
+        drop(client);
+        assert_eq!(vec![vec!["SET", "key", "1"], vec!["GET", "key"], vec!["INCR", "key"]], server.commands());
     }
 
-    fn create_command(args: &Vec<String>) -> Vec<u8> {
-        if args.len() == 1 {
-            format!("+{}\r\n{}", args[0].len(), args[0]).into_bytes()
-        } else {
+    #[test]
+    fn blocking_timeout_restored() {
+        let server = crate::mock::MockServer::new(&[]);
+        let mut client = server.client();
+        let timeout = std::time::Duration::from_secs(1);
+        client.connect.get_ref().set_read_timeout(Some(timeout)).unwrap();
 
-            let mut result = format!("*{}\r\n", args.len());
+        let raised = client.with_blocking_timeout(timeout, |client| client.connect.get_ref().read_timeout().unwrap());
+        assert_eq!(Some(timeout * 2), raised);
+        assert_eq!(Some(timeout), client.connect.get_ref().read_timeout().unwrap());
 
-            for arg in args {
-                result.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
-            }
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            client.with_blocking_timeout(timeout, |_| panic!("blocking command failed"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(Some(timeout), client.connect.get_ref().read_timeout().unwrap());
 
-            result.into_bytes()
-        }
+        let unbounded = std::time::Duration::MAX;
+        let raised = client.with_blocking_timeout(unbounded, |client| client.connect.get_ref().read_timeout().unwrap());
+        assert_eq!(None, raised);
+        assert_eq!(Some(timeout), client.connect.get_ref().read_timeout().unwrap());
     }
-}
 
-///Enum for represent redis responses
-#[derive(Debug, Eq, PartialEq)]
-pub enum Values {
-    SimpleString(String),
-    Errors(String),
-    Integers(i64),
-    BulkString(String),
-    Arrays(Vec<Values>),
-}
+    #[test]
+    fn reconnect_resolves_address_again() {
+        let first = crate::mock::MockServer::new(&[]);
+        let second = crate::mock::MockServer::new(&["+OK\r\n"]);
+        let second_addr = second.addr();
+        let original = first.addr().to_string();
+        let resolved = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let requests = std::sync::Arc::clone(&resolved);
 
-///Function for parse response redis response from tcp stream
-fn parse_response(buff: &mut Vec<u8>) -> Result<Values, Box<dyn std::error::Error>> {
-    let mut cursor = Cursor::new(buff);
+        let mut client = crate::Client::connect_host(&original).unwrap();
+        client.resolver = Some(crate::hooks::Hook(Box::new(move |addr: &str| {
+            requests.lock().unwrap().push(addr.to_string());
+            Ok(vec![second_addr])
+        })));
+        client.reconnect().unwrap();
+        client.set("key", "value").unwrap();
 
-    cursor.seek(SeekFrom::Start(0))?;
-    let mut first_byte: [u8; 1] = [0];
-    cursor.read(&mut first_byte)?;
+        assert_eq!(vec![original], *resolved.lock().unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["SET", "key", "value"]], second.commands());
+    }
 
+    #[test]
+    fn reconnect_to_address_given_as_tuple() {
+        let server = crate::mock::MockServer::sessions(&[&[], &["+PONG\r\n"]]);
+        let mut client = crate::Client::new(("127.0.0.1", server.addr().port())).unwrap();
 
-    match first_byte[0] {
-        INTEGER_BYTE => {
-            let mut l = cursor.lines();
-            if let Some(int_result) = l.next() {
-                Ok(Values::Integers(int_result?.parse()?))
-            } else {
-                Err(Box::new(std::io::Error::new(ErrorKind::InvalidInput, "Error integer value")))
-            }
-        },
-        BULK_STRING_BYTE => {
-            let mut l = cursor.lines();
+        client.reconnect().unwrap();
+        client.ping().unwrap();
+    }
 
-            let _size = if let Some(str_result) = l.next() {
-                str_result?.parse()?
-            } else {
-                0
-            };
+    #[test]
+    fn drain_stale_replies() {
+        let server = crate::mock::MockServer::new(&["+OK\r\n:1\r\n:2\r\n", "+PONG\r\n"]);
+        let mut client = server.client();
 
-            if let Some(str_result) = l.next() {
-                Ok(Values::BulkString(str_result?))
-            } else {
-                Err(Box::new(std::io::Error::new(ErrorKind::InvalidInput, "Error integer value")))
-            }
-        },
-        SIMPLE_STRING_BYTE => {
-            let mut l = cursor.lines();
+        client.set("key", "value").unwrap();
+        assert_eq!(2, client.drain(std::time::Duration::from_millis(100)));
+        client.ping().unwrap();
+    }
 
-            if let Some(int_result) = l.next() {
-                Ok(Values::SimpleString(int_result?))
-            } else {
-                Err(Box::new(std::io::Error::new(ErrorKind::InvalidInput, "Error integer value")))
-            }
-        },
-        ERROR_STRING_BYTE => {
-            let mut l = cursor.lines();
+    #[test]
+    fn single_argument_command() {
+        assert_eq!(b"*1\r\n$4\r\nPING\r\n".to_vec(), crate::Cmd::create_command(&crate::Cmd::cmd("PING").args));
+    }
+
+    #[test]
+    fn ok_status() {
+        assert!(Values::SimpleString(String::from("OK")).is_ok());
+        assert!(!Values::SimpleString(String::from("QUEUED")).is_ok());
+        assert!(!Values::BulkString(String::from("OK")).is_ok());
+    }
+
+    #[test]
+    fn resp3_marker_keeps_encoding() {
+        let plain = crate::Cmd::cmd("CONFIG").arg("GET").arg("maxmemory");
+        let marked = crate::Cmd::cmd("CONFIG").arg("GET").arg("maxmemory").expect_resp3();
+
+        assert_eq!(crate::Cmd::create_command(&plain.args), crate::Cmd::create_command(&marked.args));
+    }
+
+    #[test]
+    fn resp3_marker_selects_map_decoding() {
+        let server = crate::mock::MockServer::new(&["*2\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(
+            Values::Map(vec![(Values::BulkString(String::from("maxmemory")), Values::BulkString(String::from("0")))]),
+            crate::Cmd::cmd("CONFIG").arg("GET").arg("maxmemory").expect_resp3().execute(&mut client).unwrap()
+        );
+    }
+
+    #[test]
+    fn resp3_null() {
+        let raw_str = b"_\r\n".to_vec();
+
+        assert_eq!(Values::Nil, parse_response(&mut raw_str.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn binary_bulk_string() {
+        let raw_str = b"$3\r\n\xff\x00\xfe\r\n".to_vec();
+
+        assert_eq!(Values::BulkBytes(vec![0xff, 0x00, 0xfe]), parse_response(&mut raw_str.as_slice()).unwrap());
+    }
 
-            if let Some(int_result) = l.next() {
-                Ok(Values::Errors(int_result?))
+    #[test]
+    fn large_array_same_from_slice_and_small_buffer() {
+        let mut raw_str = b"*1000\r\n".to_vec();
+        let mut expected = Vec::new();
+        for i in 0..1000 {
+            if i % 2 == 0 {
+                raw_str.extend(format!(":{}\r\n", -i * 1_000_003).as_bytes());
+                expected.push(Values::Integers(-i * 1_000_003));
             } else {
-                Err(Box::new(std::io::Error::new(ErrorKind::InvalidInput, "Error integer value")))
+                raw_str.extend(format!("$10\r\nvalue{:05}\r\n", i).as_bytes());
+                expected.push(Values::BulkString(format!("value{:05}", i)));
             }
-        },
-        ARRAYS_BYTE => {
-            let mut line = String::new();
-            cursor.read_line(&mut line)?;
-            let line_count: i64 = line.chars()
-                .filter(|c| c.is_numeric())
-                .collect::<String>()
-                .parse()?;
+        }
 
-            let mut v: Vec<Values> = Vec::new();
+        let from_slice = parse_response(&mut raw_str.as_slice()).unwrap();
+        let from_buffer = parse_response(&mut std::io::BufReader::with_capacity(3, raw_str.as_slice())).unwrap();
 
-            let mut split = cursor.split(b'\n');
-            for _ in 0..line_count {
-                let mut first_line = split.next().unwrap()?;
-                let mut second_line = split.next().unwrap()?;
-                second_line.push(b'\n');
+        assert_eq!(Values::Arrays(expected), from_slice);
+        assert_eq!(from_slice, from_buffer);
+    }
 
-                first_line.push(b'\n');
-                first_line.append(&mut second_line);
-                v.push(parse_response(&mut first_line)?);
-            }
+    #[test]
+    fn integer_bounds() {
+        let min = format!(":{}\r\n", i64::MIN).into_bytes();
+        let overflow = b":9223372036854775808\r\n".to_vec();
+        let garbage = b":12a\r\n".to_vec();
 
-            Ok(Values::Arrays(v))
-        },
-        _ => {
-            Err(Box::new(std::io::Error::new(ErrorKind::InvalidInput, "hui")))
-        },
+        assert_eq!(Values::Integers(i64::MIN), parse_response(&mut min.as_slice()).unwrap());
+        assert!(matches!(parse_response(&mut overflow.as_slice()), Err(crate::RedisError::Protocol(_))));
+        assert!(matches!(parse_response(&mut garbage.as_slice()), Err(crate::RedisError::Protocol(_))));
     }
 
-}
+    #[test]
+    fn frame_leaves_trailing_nuls() {
+        let raw_str = b"$5\r\nhello\r\n\0\0\0".to_vec();
 
-#[cfg(test)]
-mod tests {
-    use crate::parse_response;
-    use crate::Values;
+        let (value, consumed) = crate::parse_frame(&raw_str).unwrap();
+        assert_eq!(Values::BulkString(String::from("hello")), value);
+        assert_eq!(11, consumed);
+        assert_eq!(Values::BulkString(String::from("hello")), crate::parse_single_strict(&raw_str).unwrap());
+    }
 
     #[test]
-    fn test_set_value() {
-        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
-        let result = crate::Cmd::cmd("SET").arg("\ntest\n").arg("test\n").execute(&mut client);
+    fn strict_single_rejects_trailing_data() {
+        let raw_str = b"+OK\r\n+QUEUED\r\n".to_vec();
+
+        let (value, consumed) = crate::parse_frame(&raw_str).unwrap();
+        assert_eq!(Values::SimpleString(String::from("OK")), value);
+        assert_eq!(5, consumed);
+        assert!(matches!(crate::parse_single_strict(&raw_str), Err(crate::RedisError::Protocol(_))));
+    }
+
+    #[test]
+    fn strict_single_reports_trailing_data_without_padding() {
+        match crate::parse_single_strict(b":1\r\n\0\0ab\r\n") {
+            Err(crate::RedisError::Protocol(message)) => assert_eq!("Unexpected 2 bytes of data after reply", message),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn map() {
+        let raw_str = b"%1\r\n+key\r\n:1\r\n".to_vec();
 
-        assert_eq!(true, result.is_ok());
+        assert_eq!(
+            Values::Map(vec![(Values::SimpleString(String::from("key")), Values::Integers(1))]),
+            parse_response(&mut raw_str.as_slice()).unwrap()
+        );
     }
 
     #[test]
     fn empty_string() {
-        let result = parse_response(&mut vec![0; 0]);
+        let result = parse_response(&mut [0u8; 0].as_ref());
 
-        assert_eq!(true, result.is_err());
+        assert!(result.is_err());
     }
 
     #[test]
     fn simple_string() {
-        let mut raw_str: Vec<u8> = vec![b'+', b'H', b'e', b'l', b'l', b'o', b'\r', b'\n'];
-        let result = parse_response(&mut raw_str);
+        let raw_str: Vec<u8> = vec![b'+', b'H', b'e', b'l', b'l', b'o', b'\r', b'\n'];
+        let result = parse_response(&mut raw_str.as_slice());
 
         assert_eq!(Values::SimpleString(String::from("Hello")), result.unwrap());
     }
 
     #[test]
     fn bulk_string() {
-        let mut raw_str: Vec<u8> = vec![b'$', b'4', b'\r', b'\n', b'T', b'e', b's', b't'];
-        let result = parse_response(&mut raw_str);
+        let raw_str: Vec<u8> = vec![b'$', b'4', b'\r', b'\n', b'T', b'e', b's', b't'];
+        let result = parse_response(&mut raw_str.as_slice());
 
         assert_eq!(Values::BulkString(String::from("Test")), result.unwrap());
     }
 
+    #[test]
+    #[ignore = "benchmark, run with cargo test --release -- --ignored --nocapture"]
+    fn parse_large_array_timing() {
+        let mut raw_str = b"*1000\r\n".to_vec();
+        for i in 0..1000 {
+            let element = if i % 2 == 0 { format!(":{}\r\n", i) } else { format!("$5\r\nv{:04}\r\n", i) };
+            raw_str.extend_from_slice(element.as_bytes());
+        }
+        let rounds = 1000;
+
+        let start = std::time::Instant::now();
+        for _ in 0..rounds {
+            let value = parse_response(&mut raw_str.as_slice()).unwrap();
+            assert!(matches!(value, Values::Arrays(ref elements) if elements.len() == 1000));
+        }
+        println!("1000-element array parsed in {:?} on average", start.elapsed() / rounds);
+    }
+
+    #[test]
+    fn huge_header_with_short_body_fails() {
+        for raw_str in [
+            &b"*9223372036854775807\r\n:1\r\n"[..],
+            &b"$9223372036854775807\r\nabc\r\n"[..],
+            &b"%9223372036854775807\r\n+key\r\n:1\r\n"[..],
+        ] {
+            assert!(parse_response(&mut &raw_str[..]).is_err());
+        }
+    }
+
     #[test]
     fn integer() {
-        let mut raw_str: Vec<u8> = vec![b':', b'1', b'2', b'\r', b'\n'];
-        let result = parse_response(&mut raw_str);
+        let raw_str: Vec<u8> = vec![b':', b'1', b'2', b'\r', b'\n'];
+        let result = parse_response(&mut raw_str.as_slice());
 
         assert_eq!(Values::Integers(12), result.unwrap());
     }
 
+    #[test]
+    fn integer_with_carriage_return_inside() {
+        assert!(matches!(parse_response(&mut b":1\r2\r\n".as_ref()), Err(crate::RedisError::Protocol(_))));
+        assert!(matches!(parse_response(&mut b":12\r\r\n".as_ref()), Err(crate::RedisError::Protocol(_))));
+    }
+
     #[test]
     fn negative_integer() {
-        let mut raw_str: Vec<u8> = vec![b'$', b'3', b'\r', b'\n', b'-', b'1', b'2'];
-        let result = parse_response(&mut raw_str);
+        let raw_str: Vec<u8> = vec![b'$', b'3', b'\r', b'\n', b'-', b'1', b'2'];
+        let result = parse_response(&mut raw_str.as_slice());
 
         assert_eq!(Values::BulkString(String::from("-12")), result.unwrap());
     }
 
     #[test]
     fn error() {
-        let mut raw_str: Vec<u8> = vec![b'-', b'E', b'r', b'r', b'o', b'r', b' ', b'm', b'e', b's', b's', b'a', b'g', b'e', b'\r', b'\n'];
-        let result = parse_response(&mut raw_str);
+        let raw_str: Vec<u8> = vec![b'-', b'E', b'r', b'r', b'o', b'r', b' ', b'm', b'e', b's', b's', b'a', b'g', b'e', b'\r', b'\n'];
+        let result = parse_response(&mut raw_str.as_slice());
 
         assert_eq!(Values::Errors(String::from("Error message")), result.unwrap());
     }
 
+    #[test]
+    fn nil() {
+        let raw_str = b"$-1\r\n*-1\r\n".to_vec();
+        let mut reader = raw_str.as_slice();
+
+        assert_eq!(Values::Nil, parse_response(&mut reader).unwrap());
+        assert_eq!(Values::Nil, parse_response(&mut reader).unwrap());
+    }
+
     #[test]
     fn array() {
-        let mut rawData = vec![
+        let raw_data = vec![
             b'*', b'4',
             b'\r', b'\n',
             b'$', b'3',
@@ -281,6 +1781,387 @@ mod tests {
                 Values::BulkString(String::from("t")),
                 ]
         ),
-        parse_response(&mut rawData).unwrap());
+        parse_response(&mut raw_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn query_vec_of_integers() {
+        let server = crate::mock::MockServer::new(&["*3\r\n$1\r\n1\r\n$2\r\n20\r\n$3\r\n300\r\n", "*2\r\n$1\r\n1\r\n$3\r\nabc\r\n"]);
+        let mut client = server.client();
+
+        let values: Vec<i64> = crate::Cmd::cmd("LRANGE").key("list").arg("0").arg("-1").query_vec(&mut client).unwrap();
+        assert_eq!(vec![1, 20, 300], values);
+
+        match crate::Cmd::cmd("LRANGE").key("list").arg("0").arg("-1").query_vec::<i64>(&mut client) {
+            Err(crate::RedisError::AtIndex { index, .. }) => assert_eq!(1, index),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn byte_size_of_nested_array() {
+        use crate::Values;
+
+        let value = Values::Arrays(vec![
+            Values::BulkString(String::from("hello")),
+            Values::Arrays(vec![Values::BulkString(String::from("ab")), Values::Integers(7)]),
+            Values::Map(vec![(Values::SimpleString(String::from("key")), Values::BulkBytes(vec![0xff; 10]))]),
+        ]);
+
+        assert_eq!(8 * std::mem::size_of::<Values>() + 5 + 2 + 3 + 10, value.byte_size());
+    }
+
+    #[test]
+    fn push_frame_before_reply() {
+        use std::sync::{Arc, Mutex};
+
+        let server = crate::mock::MockServer::new(&[">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nkey\r\n$5\r\nvalue\r\n"]);
+        let mut client = server.client();
+        let pushes = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&pushes);
+        client.set_push_handler(move |push| received.lock().unwrap().push(push));
+
+        assert_eq!(Some(String::from("value")), client.get("key").unwrap());
+        assert_eq!(
+            vec![crate::Values::Arrays(vec![
+                crate::Values::BulkString(String::from("invalidate")),
+                crate::Values::Arrays(vec![crate::Values::BulkString(String::from("key"))]),
+            ])],
+            *pushes.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn buffered_writes_sent_on_flush() {
+        use std::io::Read;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = crate::Client::new(listener.local_addr().unwrap()).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(std::time::Duration::from_millis(50))).unwrap();
+
+        client.set_buffered_writes(true);
+        for value in &["1", "2", "3"] {
+            client.send(crate::Cmd::cmd("RPUSH").arg("list").arg(value)).unwrap();
+        }
+        let mut buff = [0u8; 128];
+        assert!(stream.read(&mut buff).is_err());
+
+        client.flush().unwrap();
+        let expected: Vec<u8> = ["1", "2", "3"].iter()
+            .flat_map(|value| crate::Cmd::create_command(&crate::Cmd::cmd("RPUSH").arg("list").arg(value).args))
+            .collect();
+        let mut received = vec![0u8; expected.len()];
+        stream.read_exact(&mut received).unwrap();
+        assert_eq!(expected, received);
+    }
+
+    #[test]
+    fn buffered_writes_flushed_before_reply() {
+        let server = crate::mock::MockServer::new(&["$1\r\n1\r\n"]);
+        let mut client = server.client();
+        client.connect.get_ref().set_read_timeout(Some(std::time::Duration::from_secs(1))).unwrap();
+        client.set_buffered_writes(true);
+
+        assert_eq!(Values::BulkString(String::from("1")), crate::Cmd::cmd("GET").arg("key").execute(&mut client).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["GET", "key"]], server.commands());
+    }
+
+    #[test]
+    fn arg_pairs_flattened() {
+        let fields = vec![(String::from("f1"), "v1"), (String::from("f2"), "v2")];
+        let cmd = crate::Cmd::cmd("HSET").key("h").arg_pairs(fields);
+
+        assert_eq!(
+            b"*6\r\n$4\r\nHSET\r\n$1\r\nh\r\n$2\r\nf1\r\n$2\r\nv1\r\n$2\r\nf2\r\n$2\r\nv2\r\n".to_vec(),
+            crate::Cmd::create_command(&cmd.args)
+        );
+    }
+
+    #[test]
+    fn protocol_violation_passed_to_handler() {
+        use std::sync::{Arc, Mutex};
+
+        let violations = Arc::new(Mutex::new(Vec::new()));
+        for (reply, cmd) in [(":12x4\r\n", "INCR"), ("?\r\n", "PING")].iter() {
+            let server = crate::mock::MockServer::new(&[reply]);
+            let mut client = server.client();
+            let received = Arc::clone(&violations);
+            client.set_protocol_error_handler(move |violation| received.lock().unwrap().push(violation.clone()));
+
+            assert!(matches!(client.command(crate::Cmd::cmd(cmd)), Err(crate::RedisError::Protocol(_))));
+        }
+
+        let violations = violations.lock().unwrap();
+        assert_eq!(2, violations.len());
+        assert_eq!("integer", violations[0].expected);
+        assert_eq!(3, violations[0].offset);
+        assert_eq!(Some(&b'x'), violations[0].bytes.first());
+        assert_eq!("type byte", violations[1].expected);
+        assert_eq!(0, violations[1].offset);
+        assert_eq!(Some(&b'?'), violations[1].bytes.first());
+    }
+
+    #[test]
+    fn error_code_and_message() {
+        let reply = crate::Values::Errors(String::from("WRONGTYPE Operation against a key holding the wrong kind of value"));
+
+        assert_eq!(Some("WRONGTYPE"), reply.error_code());
+        assert_eq!(Some("Operation against a key holding the wrong kind of value"), reply.error_message());
+        assert_eq!(None, crate::Values::Nil.error_code());
+        assert_eq!(Some(""), crate::Values::Errors(String::from("ERR")).error_message());
+    }
+
+    #[test]
+    fn server_error_carries_code() {
+        let server = crate::mock::MockServer::new(&["-NOSCRIPT No matching script. Please use EVAL.\r\n"]);
+        let mut client = server.client();
+
+        match crate::Cmd::cmd("EVALSHA").arg("abc").arg("0").run(&mut client) {
+            Err(crate::RedisError::Server { code, message }) => {
+                assert_eq!("NOSCRIPT", code);
+                assert_eq!("No matching script. Please use EVAL.", message);
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nesting_deeper_than_limit_rejected() {
+        let nested = format!("{}:1\r\n", "*1\r\n".repeat(5));
+        let server = crate::mock::MockServer::new(&[&nested, &nested]);
+        let mut client = server.client();
+        client.set_max_nesting_depth(5);
+
+        assert!(client.command(crate::Cmd::cmd("GET").arg("key")).is_ok());
+        client.set_max_nesting_depth(4);
+        match client.command(crate::Cmd::cmd("GET").arg("key")) {
+            Err(crate::RedisError::Protocol(message)) => assert_eq!("max nesting depth exceeded", message),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn deep_nesting_fails_without_stack_overflow() {
+        let nested = format!("{}:1\r\n", "*1\r\n".repeat(1_000_000));
+
+        assert!(matches!(parse_response(&mut nested.as_bytes()), Err(crate::RedisError::Protocol(_))));
+    }
+
+    #[test]
+    fn execute_into_reuses_buffer() {
+        let replies: Vec<String> = (0..100).map(|i| format!("*2\r\n:{}\r\n$5\r\nvalue\r\n", i)).collect();
+        let server = crate::mock::MockServer::new(&replies.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut client = server.client();
+        let cmd = crate::Cmd::cmd("GET").arg("key");
+        let mut buf = Vec::new();
+
+        for (i, reply) in replies.iter().enumerate() {
+            let value = client.execute_into(&cmd, &mut buf).unwrap();
+            assert_eq!(
+                crate::Values::Arrays(vec![crate::Values::Integers(i as i64), crate::Values::BulkString(String::from("value"))]),
+                value
+            );
+            assert_eq!(reply.as_bytes(), buf.as_slice());
+        }
+        drop(client);
+        assert_eq!(100, server.commands().len());
+    }
+
+    #[test]
+    fn routing_key_not_encoded() {
+        let plain = crate::Cmd::cmd("EVAL").arg("return 1").arg("0");
+        let routed = crate::Cmd::cmd("EVAL").arg("return 1").arg("0").with_routing_key("user:{42}");
+
+        assert_eq!(None, plain.routing_key());
+        assert_eq!(Some("user:{42}"), routed.routing_key());
+        assert_eq!(crate::Cmd::create_command(&plain.args), crate::Cmd::create_command(&routed.args));
+        assert_eq!(Some("first"), crate::Cmd::cmd("MGET").key("first").key("second").routing_key());
+    }
+
+    #[test]
+    fn crc16_slot_of_keys() {
+        assert_eq!(12739, crate::crc16_slot("123456789"));
+        assert_eq!(12182, crate::crc16_slot("foo"));
+        assert_eq!(crate::crc16_slot("42"), crate::crc16_slot("user:{42}"));
+        assert_ne!(crate::crc16_slot("user"), crate::crc16_slot("{}user"));
+    }
+
+    #[test]
+    fn interrupted_array_keeps_parsed_elements() {
+        let raw = b"*3\r\n$3\r\none\r\n:2\r\n";
+
+        match parse_response(&mut raw.as_ref()) {
+            Err(crate::RedisError::PartialArray { error, partial }) => {
+                assert!(matches!(*error, crate::RedisError::Io(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+                assert_eq!(vec![Values::BulkString(String::from("one")), Values::Integers(2)], partial);
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn last_redirect_cleared_by_next_reply() {
+        let server = crate::mock::MockServer::new(&[
+            "-MOVED 3999 127.0.0.1:6381\r\n", "-ERR wrong number of arguments\r\n", "$5\r\nvalue\r\n",
+        ]);
+        let mut client = server.client();
+
+        let error = client.get("key").unwrap_err();
+        let expected = crate::Redirect {
+            kind: crate::RedirectKind::Moved,
+            slot: 3999,
+            addr: String::from("127.0.0.1:6381"),
+        };
+        assert_eq!(Some(expected.clone()), error.redirect());
+        assert_eq!(Some(expected.clone()), client.last_redirect());
+        assert!(client.get("key").is_err());
+        assert_eq!(Some(expected), client.last_redirect());
+        assert_eq!(Some(String::from("value")), client.get("key").unwrap());
+        assert_eq!(None, client.last_redirect());
+    }
+
+    #[test]
+    fn parse_ask_redirect() {
+        let error = crate::RedisError::server(String::from("ASK 12182 10.0.0.5:7002"));
+        assert_eq!(
+            Some(crate::Redirect { kind: crate::RedirectKind::Ask, slot: 12182, addr: String::from("10.0.0.5:7002") }),
+            error.redirect()
+        );
+        assert_eq!(None, crate::RedisError::server(String::from("MOVED nonsense")).redirect());
+    }
+
+    #[test]
+    fn reset_state_restores_defaults() {
+        let server = crate::mock::MockServer::new(&[
+            "%1\r\n$5\r\nproto\r\n:3\r\n",
+            "+OK\r\n",
+            "-MOVED 3999 127.0.0.1:6381\r\n",
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n",
+        ]);
+        let mut client = server.client();
+        client.set_type_cache_ttl(Some(std::time::Duration::from_secs(60)));
+
+        client.hello(3).unwrap();
+        client.select(2).unwrap();
+        assert!(client.get("key").is_err());
+        client.subscribe(&["news"]).unwrap();
+        client.capabilities = Some(crate::Capabilities {
+            protocol: 3,
+            server_version: String::from("7.2.4"),
+            command_count: 240,
+        });
+        client.arities = Some([(String::from("get"), Some(2))].iter().cloned().collect());
+        assert!(client.pubsub.is_active());
+        assert!(client.last_redirect().is_some());
+        assert_eq!(3, client.protocol);
+
+        client.reset_state();
+        assert!(!client.pubsub.is_active());
+        assert_eq!(0, client.db);
+        assert_eq!(2, client.protocol);
+        assert_eq!(None, client.capabilities());
+        assert_eq!(None, client.last_redirect());
+        assert_eq!(Some(0), client.arities.as_ref().map(|arities| arities.len()));
+        drop(client);
+        assert_eq!(
+            vec![vec!["HELLO", "3"], vec!["SELECT", "2"], vec!["GET", "key"], vec!["SUBSCRIBE", "news"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn reply_after_deadline_not_taken_by_next_command() {
+        let delay = std::time::Duration::from_millis(100);
+        let (addr, server) = crate::mock::slow_then_fast(delay, "$3\r\nold\r\n", "$3\r\nnew\r\n");
+        let mut client = crate::Client::new(addr).unwrap();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+
+        let deadline = clock.now() + std::time::Duration::from_millis(50);
+        let result = crate::Cmd::cmd("GET").arg("slow").execute_deadline(&mut client, deadline);
+        assert!(matches!(result, Err(crate::RedisError::TimedOut)));
+        assert_eq!(Some(String::from("new")), client.get("fast").unwrap());
+
+        assert_eq!(vec![vec!["GET", "slow"], vec!["GET", "fast"]], server.join().unwrap());
+    }
+
+    #[test]
+    fn next_command_fails_when_timed_out_connection_cant_reconnect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = crate::Client::new(listener.local_addr().unwrap()).unwrap();
+        let accepted = listener.accept().unwrap();
+        drop(listener);
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+
+        let deadline = clock.now() + std::time::Duration::from_millis(20);
+        let result = crate::Cmd::cmd("GET").arg("slow").execute_deadline(&mut client, deadline);
+        assert!(matches!(result, Err(crate::RedisError::TimedOut)));
+        match client.get("key") {
+            Err(crate::RedisError::Io(e)) => assert_eq!(std::io::ErrorKind::NotConnected, e.kind()),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        drop(accepted);
+    }
+
+    #[test]
+    fn deadline_command_resent_after_reconnect_and_observed() {
+        let server = crate::mock::MockServer::sessions(&[
+            &["$6\r\nembstr\r\n"],
+            &["$5\r\nvalue\r\n", "$3\r\nraw\r\n"],
+        ]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+        let changes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let observed = changes.clone();
+        client.watch_encoding("key", move |encoding| observed.lock().unwrap().push(encoding.to_string())).unwrap();
+
+        let deadline = clock.now() + std::time::Duration::from_secs(5);
+        let value = crate::Cmd::cmd("GET").key("key").execute_deadline(&mut client, deadline).unwrap();
+        assert_eq!(Values::BulkString(String::from("value")), value);
+        assert_eq!(vec![String::from("raw")], *changes.lock().unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["OBJECT", "ENCODING", "key"], vec!["GET", "key"], vec!["OBJECT", "ENCODING", "key"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn execute_into_shares_reply_handling() {
+        let moved = "-MOVED 3999 127.0.0.1:6381\r\n";
+        let push = ">2\r\n$7\r\nmessage\r\n$4\r\nnews\r\n";
+        let server = crate::mock::MockServer::new(&[&format!("{}{}", push, moved), "+OK\r\n"]);
+        let mut client = server.client();
+        let pushes = std::sync::Arc::new(std::sync::Mutex::new(0));
+        let counter = pushes.clone();
+        client.set_push_handler(move |_| *counter.lock().unwrap() += 1);
+        let cmd = crate::Cmd::cmd("GET").arg("key");
+        let mut buf = Vec::new();
+
+        client.execute_into(&cmd, &mut buf).unwrap();
+        assert_eq!(moved.as_bytes(), buf.as_slice());
+        assert_eq!(1, *pushes.lock().unwrap());
+        assert!(client.last_redirect().is_some());
+
+        client.execute_into(&cmd, &mut buf).unwrap();
+        assert_eq!(b"+OK\r\n", buf.as_slice());
+        assert_eq!(None, client.last_redirect());
+    }
+
+    #[test]
+    fn execute_into_resends_read_only_command_after_reconnect() {
+        let server = crate::mock::MockServer::sessions(&[&[], &["$5\r\nvalue\r\n"]]);
+        let mut client = server.client();
+        client.set_auto_reconnect(true);
+        let mut buf = Vec::new();
+
+        let value = client.execute_into(&crate::Cmd::cmd("GET").arg("key"), &mut buf).unwrap();
+        assert_eq!(crate::Values::BulkString(String::from("value")), value);
+        assert_eq!(b"$5\r\nvalue\r\n", buf.as_slice());
     }
 }
@@ -0,0 +1,108 @@
+use std::{io::{BufReader, Write}, net::{SocketAddr, TcpListener, TcpStream}, thread::{self, JoinHandle}, time::Duration};
+
+use crate::{parse_response, Client, Values};
+
+///Stub redis server for tests, answers every received command with next scripted reply
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    handle: JoinHandle<Vec<Vec<String>>>,
+}
+
+impl MockServer {
+    ///Start server accepting one connection, close it after last reply
+    pub(crate) fn new(replies: &[&str]) -> Self {
+        Self::sessions(&[replies])
+    }
+
+    ///Start server accepting one connection with replies which aren't UTF-8
+    pub(crate) fn with_bytes(replies: &[&[u8]]) -> Self {
+        Self::start(vec![replies.iter().map(|r| r.to_vec()).collect()])
+    }
+
+    ///Start server accepting one connection per script, each connection is closed after its last reply
+    pub(crate) fn sessions(scripts: &[&[&str]]) -> Self {
+        Self::start(scripts.iter()
+            .map(|replies| replies.iter().map(|r| r.as_bytes().to_vec()).collect())
+            .collect())
+    }
+
+    fn start(scripts: Vec<Vec<Vec<u8>>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut commands = Vec::new();
+
+            for replies in scripts {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                for reply in replies {
+                    match read_command(&mut reader) {
+                        Some(command) => commands.push(command),
+                        None => break,
+                    }
+
+                    reader.get_mut().write_all(&reply).unwrap();
+                }
+            }
+
+            commands
+        });
+
+        MockServer { addr, handle }
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub(crate) fn client(&self) -> Client {
+        Client::new(self.addr).unwrap()
+    }
+
+    ///Wait for server to finish and return commands received by all connections
+    pub(crate) fn commands(self) -> Vec<Vec<String>> {
+        self.handle.join().unwrap()
+    }
+}
+
+fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<String>> {
+    match parse_response(reader) {
+        Ok(Values::Arrays(args)) => Some(args.into_iter().map(|arg| match arg {
+            Values::BulkString(s) => s,
+            Values::BulkBytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            other => panic!("Unexpected command argument {:?}", other),
+        }).collect()),
+        _ => None,
+    }
+}
+
+///Start server accepting two connections, first one gets `slow_reply` only after `delay` while second one
+///got `fast_reply` at once, returns commands received by both
+pub(crate) fn slow_then_fast(
+    delay: Duration,
+    slow_reply: &'static str,
+    fast_reply: &'static str,
+) -> (SocketAddr, JoinHandle<Vec<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let handle = thread::spawn(move || {
+        let mut commands = Vec::new();
+        let (stream, _) = listener.accept().unwrap();
+        let mut slow = BufReader::new(stream);
+        commands.extend(read_command(&mut slow));
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut fast = BufReader::new(stream);
+        commands.extend(read_command(&mut fast));
+        fast.get_mut().write_all(fast_reply.as_bytes()).unwrap();
+        thread::sleep(delay);
+        let _ = slow.get_mut().write_all(slow_reply.as_bytes());
+
+        commands
+    });
+
+    (addr, handle)
+}
@@ -0,0 +1,129 @@
+use crate::{Client, Cmd, RedisError, Values};
+
+///Several commands sent at once, replies are read after all commands are written
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    commands: Vec<Cmd>,
+    chunk_size: Option<usize>,
+}
+
+impl Pipeline {
+    ///Start create pipeline
+    ///```
+    ///use redis_client::{Cmd, Pipeline};
+    ///
+    ///let pipeline = Pipeline::new()
+    ///    .command(Cmd::cmd("SET").arg("key").arg("value"))
+    ///    .command(Cmd::cmd("GET").arg("key"));
+    ///```
+    pub fn new() -> Self {
+        Pipeline::default()
+    }
+
+    ///Add command to pipeline
+    pub fn command(mut self, cmd: Cmd) -> Self {
+        self.commands.push(cmd);
+
+        self
+    }
+
+    ///Write encoded commands to socket whenever buffer exceeds `bytes` instead of one write for whole pipeline
+    ///```
+    ///use redis_client::{Cmd, Pipeline};
+    ///
+    ///let pipeline = Pipeline::new()
+    ///    .with_chunk_size(64 * 1024)
+    ///    .command(Cmd::cmd("INCR").arg("counter"));
+    ///```
+    pub fn with_chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = Some(bytes);
+
+        self
+    }
+
+    ///Execute all commands and return their replies in order
+    ///```no_run
+    ///use redis_client::{Cmd, Client, Pipeline};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let replies = Pipeline::new()
+    ///    .command(Cmd::cmd("INCR").arg("counter"))
+    ///    .command(Cmd::cmd("INCR").arg("counter"))
+    ///    .execute(&mut client);
+    ///```
+    pub fn execute(self, conn: &mut Client) -> Result<Vec<Values>, RedisError> {
+        for cmd in &self.commands {
+            cmd.validate(conn)?;
+        }
+
+        let mut buff = Vec::new();
+        for cmd in &self.commands {
+            buff.extend_from_slice(&cmd.encode(conn));
+
+            if matches!(self.chunk_size, Some(size) if buff.len() >= size) {
+                conn.write(&buff)?;
+                buff.clear();
+            }
+        }
+        if !buff.is_empty() {
+            conn.write(&buff)?;
+        }
+
+        self.commands.iter().map(|_| conn.read_pending_reply()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{Cmd, Pipeline, RedisError, Values};
+
+    #[test]
+    fn pipeline_replies_in_order() {
+        let server = MockServer::new(&["+OK\r\n", "$5\r\nvalue\r\n"]);
+        let mut client = server.client();
+
+        let replies = Pipeline::new()
+            .command(Cmd::cmd("SET").arg("key").arg("value"))
+            .command(Cmd::cmd("GET").arg("key"))
+            .execute(&mut client)
+            .unwrap();
+
+        assert_eq!(
+            vec![Values::SimpleString(String::from("OK")), Values::BulkString(String::from("value"))],
+            replies
+        );
+    }
+
+    #[test]
+    fn chunked_pipeline() {
+        let replies: Vec<String> = (1..=100).map(|i| format!(":{}\r\n", i)).collect();
+        let server = MockServer::new(&replies.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut client = server.client();
+
+        let pipeline = (0..100).fold(Pipeline::new().with_chunk_size(64), |pipeline, _| {
+            pipeline.command(Cmd::cmd("INCR").arg("counter"))
+        });
+
+        assert_eq!((1..=100).map(Values::Integers).collect::<Vec<_>>(), pipeline.execute(&mut client).unwrap());
+        drop(client);
+        assert_eq!(100, server.commands().len());
+    }
+
+    #[test]
+    fn pending_replies_after_timeout_not_taken_by_next_command() {
+        let delay = std::time::Duration::from_millis(100);
+        let (addr, server) = crate::mock::slow_then_fast(delay, "+OK\r\n", "$3\r\nnew\r\n");
+        let mut client = crate::Client::new(addr).unwrap();
+        client.connect.get_ref().set_read_timeout(Some(std::time::Duration::from_millis(50))).unwrap();
+
+        let replies = Pipeline::new()
+            .command(Cmd::cmd("SET").arg("slow").arg("1"))
+            .command(Cmd::cmd("GET").arg("slow"))
+            .execute(&mut client);
+        assert!(matches!(replies, Err(RedisError::Io(_))));
+        assert_eq!(Some(String::from("new")), client.get("fast").unwrap());
+
+        assert_eq!(vec![vec!["SET", "slow", "1"], vec!["GET", "fast"]], server.join().unwrap());
+    }
+}
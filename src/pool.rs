@@ -0,0 +1,192 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+};
+
+use crate::{Client, RedisError};
+
+///Pool of clients connected to same address, idle clients are reused by `get`
+///```no_run
+///use redis_client::Pool;
+///let pool = Pool::new("127.0.0.1:6379");
+///
+///let mut client = pool.get().unwrap();
+///client.set("key", "value").unwrap();
+///```
+#[derive(Debug)]
+pub struct Pool {
+    addr: String,
+    idle: Mutex<Vec<Client>>,
+    ///Idle client answers `PING` before it's handed out
+    validation: bool,
+    in_use: AtomicUsize,
+    created: AtomicUsize,
+    recycled: AtomicUsize,
+}
+
+///Counters of `Pool`, taken by `Pool::stats`
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct PoolStats {
+    ///Clients waiting in pool
+    pub idle: usize,
+    ///Clients handed out and not returned yet
+    pub in_use: usize,
+    ///Clients connected by pool since it was created
+    pub created: usize,
+    ///Idle clients dropped because they didn't answer `PING`
+    pub recycled: usize,
+}
+
+impl Pool {
+    ///Create empty pool, clients are connected on demand
+    pub fn new<A: ToString>(addr: A) -> Self {
+        Pool {
+            addr: addr.to_string(),
+            idle: Mutex::new(Vec::new()),
+            validation: true,
+            in_use: AtomicUsize::new(0),
+            created: AtomicUsize::new(0),
+            recycled: AtomicUsize::new(0),
+        }
+    }
+
+    ///Check idle client by `PING` before handing it out, dead one is replaced by new connection, on by default
+    ///
+    ///Check costs round trip on every `get`
+    pub fn with_validation(mut self, validation: bool) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    ///Take idle client or connect new one, client returns to pool when dropped
+    pub fn get(&self) -> Result<PooledClient<'_>, RedisError> {
+        loop {
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            let client = match idle {
+                Some(mut client) => {
+                    if self.validation && !client.is_alive() {
+                        self.recycled.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    client
+                },
+                None => {
+                    let client = Client::new(self.addr.as_str())?;
+                    self.created.fetch_add(1, Ordering::Relaxed);
+
+                    client
+                },
+            };
+            self.in_use.fetch_add(1, Ordering::Relaxed);
+
+            return Ok(PooledClient { pool: self, client: Some(client) });
+        }
+    }
+
+    ///Current counters of pool, counters change concurrently so they may be slightly inconsistent with each other
+    ///```no_run
+    ///use redis_client::Pool;
+    ///let pool = Pool::new("127.0.0.1:6379");
+    ///
+    ///let stats = pool.stats();
+    ///println!("{} idle, {} in use", stats.idle, stats.in_use);
+    ///```
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            idle: self.idle.lock().unwrap_or_else(|e| e.into_inner()).len(),
+            in_use: self.in_use.load(Ordering::Relaxed),
+            created: self.created.load(Ordering::Relaxed),
+            recycled: self.recycled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+///Client taken from `Pool`, returned to it on drop
+#[derive(Debug)]
+pub struct PooledClient<'a> {
+    pool: &'a Pool,
+    client: Option<Client>,
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().expect("client is taken only on drop")
+    }
+}
+
+impl DerefMut for PooledClient<'_> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().expect("client is taken only on drop")
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.idle.lock().unwrap_or_else(|e| e.into_inner()).push(client);
+            self.pool.in_use.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use super::{Pool, PoolStats};
+    use crate::mock::MockServer;
+
+    #[test]
+    fn idle_client_reused() {
+        let server = MockServer::new(&["+OK\r\n", "+PONG\r\n", "$5\r\nvalue\r\n"]);
+        let pool = Pool::new(server.addr());
+
+        pool.get().unwrap().set("key", "value").unwrap();
+        assert_eq!(Some(String::from("value")), pool.get().unwrap().get("key").unwrap());
+        drop(pool);
+        assert_eq!(
+            vec![vec!["SET", "key", "value"], vec!["PING"], vec!["GET", "key"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn dead_client_replaced_on_checkout() {
+        let server = MockServer::sessions(&[&["+OK\r\n"], &["$5\r\nvalue\r\n"]]);
+        let pool = Pool::new(server.addr());
+
+        pool.get().unwrap().set("key", "value").unwrap();
+        assert_eq!(Some(String::from("value")), pool.get().unwrap().get("key").unwrap());
+        assert_eq!(PoolStats { idle: 1, in_use: 0, created: 2, recycled: 1 }, pool.stats());
+        drop(pool);
+        assert_eq!(vec![vec!["SET", "key", "value"], vec!["GET", "key"]], server.commands());
+    }
+
+    #[test]
+    fn stats_after_concurrent_checkouts() {
+        //Connections wait in backlog of listener, nothing is sent on them
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let pool = Pool::new(listener.local_addr().unwrap()).with_validation(false);
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        let client = pool.get().unwrap();
+                        assert!(pool.stats().in_use >= 1);
+                        drop(client);
+                    }
+                });
+            }
+        });
+
+        let stats = pool.stats();
+        assert_eq!(0, stats.in_use);
+        assert_eq!(stats.created, stats.idle);
+        assert!(stats.created >= 1 && stats.created <= 8);
+        assert_eq!(0, stats.recycled);
+    }
+}
@@ -0,0 +1,1085 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, VecDeque},
+    convert::TryFrom,
+    io::{self, ErrorKind},
+    sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver, SyncSender}, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{connection::Transport, is_disconnect, Client, Cmd, FromRedisValue, RedisError, Values};
+
+///Message received from subscribed channel
+#[derive(Debug, Eq, PartialEq)]
+pub enum PubSubMessage {
+    ///Message published to subscribed channel, `payload` is lossy UTF-8 of `payload_bytes`
+    Message { channel: String, payload: String, payload_bytes: Vec<u8> },
+    ///Message published to channel matching subscribed pattern
+    PMessage { pattern: String, channel: String, payload: String, payload_bytes: Vec<u8> },
+    ///Message published to subscribed shard channel
+    SMessage { channel: String, payload: String, payload_bytes: Vec<u8> },
+    ///Connection was lost and subscriptions were restored, messages published meanwhile are missed
+    Reconnected,
+}
+
+impl PubSubMessage {
+    ///Channel message was published to, `None` for `Reconnected`
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            PubSubMessage::Message { channel, .. }
+            | PubSubMessage::PMessage { channel, .. }
+            | PubSubMessage::SMessage { channel, .. } => Some(channel),
+            PubSubMessage::Reconnected => None,
+        }
+    }
+}
+
+///Change of key reported by keyspace notification
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KeyspaceEvent {
+    pub key: String,
+    ///Name of command kind which changed key, like `set`, `del` or `expired`
+    pub event: String,
+}
+
+impl KeyspaceEvent {
+    ///Parse message of `__keyspace@<db>__:<key>` or `__keyevent@<db>__:<event>` channel, `None` for other messages
+    ///```
+    ///use redis_client::{KeyspaceEvent, PubSubMessage};
+    ///
+    ///let message = PubSubMessage::Message {
+    ///    channel: String::from("__keyspace@0__:user:1"),
+    ///    payload: String::from("del"),
+    ///    payload_bytes: b"del".to_vec(),
+    ///};
+    ///let event = KeyspaceEvent::from_message(&message).unwrap();
+    ///assert_eq!("user:1", event.key);
+    ///```
+    pub fn from_message(message: &PubSubMessage) -> Option<KeyspaceEvent> {
+        let (channel, payload) = match message {
+            PubSubMessage::Message { channel, payload, .. } | PubSubMessage::PMessage { channel, payload, .. } => {
+                (channel, payload)
+            },
+            _ => return None,
+        };
+
+        let (kind, rest) = channel.strip_prefix("__")?.split_once('@')?;
+        let (db, name) = rest.split_once("__:")?;
+        db.parse::<u32>().ok()?;
+
+        match kind {
+            "keyspace" => Some(KeyspaceEvent { key: name.to_string(), event: payload.clone() }),
+            "keyevent" => Some(KeyspaceEvent { key: payload.clone(), event: name.to_string() }),
+            _ => None,
+        }
+    }
+}
+
+///What `Subscriber::recv_from` does with messages of other channels
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OtherChannels {
+    ///Keep them for later `recv` and `recv_from`
+    Buffer,
+    ///Discard them
+    Drop,
+}
+
+///Count of received messages `Subscriber` holds unless created by `Subscriber::with_capacity`
+const DEFAULT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+///How many times `Subscriber` tries to restore lost connection
+const RECONNECT_ATTEMPTS: u32 = 3;
+///Delay before first reconnect attempt, doubled after every failed attempt
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+///Pub/sub state of connection
+#[derive(Debug, Default)]
+pub(crate) struct PubSubState {
+    ///Count of active subscriptions reported by server
+    count: i64,
+    ///Count of active shard channel subscriptions, server reports it separately
+    shard_count: i64,
+    channels: BTreeSet<String>,
+    patterns: BTreeSet<String>,
+    shard_channels: BTreeSet<String>,
+    ///Messages received while waiting for subscription confirmations
+    pending: VecDeque<PubSubMessage>,
+}
+
+impl PubSubState {
+    pub(crate) fn is_active(&self) -> bool {
+        self.count > 0 || self.shard_count > 0
+    }
+
+    fn set_count(&mut self, sharded: bool, count: i64) {
+        if sharded {
+            self.shard_count = count;
+        } else {
+            self.count = count;
+        }
+    }
+}
+
+///Commands server accepts from connection in subscriber mode
+pub(crate) const SUBSCRIBER_MODE_COMMANDS: &[&str] = &[
+    "SUBSCRIBE", "PSUBSCRIBE", "SSUBSCRIBE", "UNSUBSCRIBE", "PUNSUBSCRIBE", "SUNSUBSCRIBE", "PING", "RESET", "QUIT",
+];
+
+pub(crate) fn allowed_in_subscriber_mode(command: &str) -> bool {
+    SUBSCRIBER_MODE_COMMANDS.iter().any(|allowed| allowed.eq_ignore_ascii_case(command))
+}
+
+///Frame received by connection in subscriber mode
+enum Frame {
+    Message(PubSubMessage),
+    ///Subscribe or unsubscribe confirmation with count of active subscriptions
+    Subscription { sharded: bool, count: i64 },
+    Pong,
+}
+
+impl Frame {
+    fn from_values(value: Values) -> Result<Self, RedisError> {
+        let mut parts = match value {
+            Values::Arrays(parts) => parts.into_iter(),
+            Values::Errors(e) => return Err(RedisError::server(e)),
+            //RESP3 connection answers `PING` in subscriber mode as usual
+            Values::SimpleString(ref s) if s == "PONG" => return Ok(Frame::Pong),
+            other => return Err(RedisError::unexpected_reply("array", &other)),
+        };
+
+        let kind = next_string(&mut parts)?;
+        match kind.as_str() {
+            "message" => {
+                let channel = next_string(&mut parts)?;
+                let payload_bytes = next_bytes(&mut parts)?;
+
+                Ok(Frame::Message(PubSubMessage::Message {
+                    channel,
+                    payload: String::from_utf8_lossy(&payload_bytes).into_owned(),
+                    payload_bytes,
+                }))
+            },
+            "pmessage" => {
+                let pattern = next_string(&mut parts)?;
+                let channel = next_string(&mut parts)?;
+                let payload_bytes = next_bytes(&mut parts)?;
+
+                Ok(Frame::Message(PubSubMessage::PMessage {
+                    pattern,
+                    channel,
+                    payload: String::from_utf8_lossy(&payload_bytes).into_owned(),
+                    payload_bytes,
+                }))
+            },
+            "smessage" => {
+                let channel = next_string(&mut parts)?;
+                let payload_bytes = next_bytes(&mut parts)?;
+
+                Ok(Frame::Message(PubSubMessage::SMessage {
+                    channel,
+                    payload: String::from_utf8_lossy(&payload_bytes).into_owned(),
+                    payload_bytes,
+                }))
+            },
+            "subscribe" | "unsubscribe" | "psubscribe" | "punsubscribe" | "ssubscribe" | "sunsubscribe" => {
+                //Channel is nil in confirmation of unsubscribe from all while nothing is subscribed
+                parts.next();
+                let sharded = kind == "ssubscribe" || kind == "sunsubscribe";
+                match parts.next() {
+                    Some(Values::Integers(count)) => Ok(Frame::Subscription { sharded, count }),
+                    Some(other) => Err(RedisError::unexpected_reply("integer", &other)),
+                    None => Err(RedisError::Protocol(String::from("Subscription count is missing"))),
+                }
+            },
+            "pong" => Ok(Frame::Pong),
+            _ => Err(RedisError::Protocol(format!("Unknown pub/sub frame kind {}", kind))),
+        }
+    }
+}
+
+fn next_string<I: Iterator<Item = Values>>(parts: &mut I) -> Result<String, RedisError> {
+    match parts.next() {
+        Some(Values::BulkString(s)) | Some(Values::SimpleString(s)) => Ok(s),
+        Some(other) => Err(RedisError::unexpected_reply("string", &other)),
+        None => Err(RedisError::Protocol(String::from("Pub/sub frame is too short"))),
+    }
+}
+
+///Payload of message, kept byte exact
+fn next_bytes<I: Iterator<Item = Values>>(parts: &mut I) -> Result<Vec<u8>, RedisError> {
+    match parts.next() {
+        Some(Values::BulkBytes(bytes)) => Ok(bytes),
+        Some(Values::BulkString(s)) | Some(Values::SimpleString(s)) => Ok(s.into_bytes()),
+        Some(other) => Err(RedisError::unexpected_reply("string", &other)),
+        None => Err(RedisError::Protocol(String::from("Pub/sub frame is too short"))),
+    }
+}
+
+///Consumer of subscribed channels, socket is read on background thread
+///
+///At most capacity of received messages wait for `recv`, while queue is full background thread stops reading socket
+///so slow consumer pushes back on server instead of growing memory
+///
+///Lost connection is restored automatically with all subscriptions, `PubSubMessage::Reconnected` is emitted then
+#[derive(Debug)]
+pub struct Subscriber {
+    receiver: Receiver<Result<PubSubMessage, RedisError>>,
+    stream: Arc<Mutex<Transport>>,
+    closed: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+    ///Messages skipped by `recv_from`, returned before new ones
+    skipped: RefCell<VecDeque<PubSubMessage>>,
+    other_channels: OtherChannels,
+}
+
+impl Subscriber {
+    ///Subscribe client to channels keeping at most `capacity` received messages, `Client::into_subscriber` keeps 1024
+    ///
+    ///Socket isn't read while queue is full, server buffers messages meanwhile and may disconnect slow subscriber
+    ///by its `client-output-buffer-limit`
+    ///```no_run
+    ///use redis_client::{Client, Subscriber};
+    ///let client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let subscriber = Subscriber::with_capacity(client, &["news"], 16).unwrap();
+    ///```
+    pub fn with_capacity(mut client: Client, channels: &[&str], capacity: usize) -> Result<Subscriber, RedisError> {
+        client.subscribe(channels)?;
+
+        Subscriber::start(client, capacity, None)
+    }
+
+    ///Subscribe client to channels sending `PING` after every `interval` without frames
+    ///
+    ///Connection not answering `PING` within another `interval` is considered dead and restored like lost one,
+    ///so silently dropped connection doesn't block `recv` forever
+    ///```no_run
+    ///use std::time::Duration;
+    ///use redis_client::{Client, Subscriber};
+    ///let client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let subscriber = Subscriber::with_keepalive(client, &["news"], Duration::from_secs(30)).unwrap();
+    ///```
+    pub fn with_keepalive(mut client: Client, channels: &[&str], interval: Duration) -> Result<Subscriber, RedisError> {
+        if interval == Duration::from_secs(0) {
+            return Err(RedisError::InvalidArgument(String::from("Keepalive interval must be positive")));
+        }
+        client.subscribe(channels)?;
+
+        Subscriber::start(client, DEFAULT_SUBSCRIBER_CAPACITY, Some(interval))
+    }
+
+    ///Start reading messages of subscriptions client already has
+    fn start(client: Client, capacity: usize, keepalive: Option<Duration>) -> Result<Subscriber, RedisError> {
+        let stream = Arc::new(Mutex::new(client.connect.get_ref().try_clone()?));
+        let closed = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        let reader = {
+            let stream = Arc::clone(&stream);
+            let closed = Arc::clone(&closed);
+
+            thread::spawn(move || client.deliver_messages(sender, &stream, &closed, keepalive))
+        };
+
+        Ok(Subscriber {
+            receiver,
+            stream,
+            closed,
+            reader: Some(reader),
+            skipped: RefCell::new(VecDeque::new()),
+            other_channels: OtherChannels::Buffer,
+        })
+    }
+
+    ///Block until next message arrives
+    ///```no_run
+    ///use redis_client::Client;
+    ///let subscriber = Client::new("127.0.0.1:6379").unwrap()
+    ///    .into_subscriber(&["news"])
+    ///    .unwrap();
+    ///
+    ///let message = subscriber.recv();
+    ///```
+    pub fn recv(&self) -> Result<PubSubMessage, RedisError> {
+        if let Some(message) = self.skipped.borrow_mut().pop_front() {
+            return Ok(message);
+        }
+
+        self.receive()
+    }
+
+    ///Block until message of given channel arrives, messages of other channels are buffered by default
+    ///```no_run
+    ///use redis_client::{Client, OtherChannels};
+    ///let mut subscriber = Client::new("127.0.0.1:6379").unwrap()
+    ///    .into_subscriber(&["news", "weather"])
+    ///    .unwrap();
+    ///
+    ///subscriber.set_other_channels(OtherChannels::Drop);
+    ///let message = subscriber.recv_from("news");
+    ///```
+    pub fn recv_from(&mut self, channel: &str) -> Result<PubSubMessage, RedisError> {
+        let skipped = self.skipped.get_mut();
+        if let Some(position) = skipped.iter().position(|message| message.channel() == Some(channel)) {
+            return Ok(skipped.remove(position).expect("position is in buffer"));
+        }
+
+        loop {
+            let message = self.receive()?;
+            if message.channel() == Some(channel) {
+                return Ok(message);
+            }
+
+            if self.other_channels == OtherChannels::Buffer {
+                self.skipped.get_mut().push_back(message);
+            }
+        }
+    }
+
+    ///Block until next keyspace notification arrives, other messages are skipped
+    ///
+    ///`PubSubMessage::Reconnected` is skipped too, notifications published while connection was lost are missed
+    ///```no_run
+    ///use redis_client::Client;
+    ///let subscriber = Client::new("127.0.0.1:6379").unwrap()
+    ///    .watch_keyspace(0, "user:*")
+    ///    .unwrap();
+    ///
+    ///let event = subscriber.recv_keyspace().unwrap();
+    ///println!("{} {}", event.event, event.key);
+    ///```
+    pub fn recv_keyspace(&self) -> Result<KeyspaceEvent, RedisError> {
+        loop {
+            if let Some(event) = KeyspaceEvent::from_message(&self.recv()?) {
+                return Ok(event);
+            }
+        }
+    }
+
+    ///Choose what `recv_from` does with messages of other channels
+    pub fn set_other_channels(&mut self, policy: OtherChannels) {
+        self.other_channels = policy;
+    }
+
+    fn receive(&self) -> Result<PubSubMessage, RedisError> {
+        self.receiver.recv().unwrap_or_else(|_| {
+            Err(RedisError::Io(std::io::Error::new(ErrorKind::BrokenPipe, "Subscriber connection closed")))
+        })
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        if let Ok(stream) = self.stream.lock() {
+            let _ = stream.shutdown();
+        }
+
+        if let Some(reader) = self.reader.take() {
+            //Reader blocked by full queue exits once queue is drained
+            self.receiver.iter().for_each(drop);
+            let _ = reader.join();
+        }
+    }
+}
+
+impl Client {
+    ///Subscribe to channels, returns count of active subscriptions of connection after last confirmation
+    ///
+    ///Until all subscriptions are removed client accepts only pub/sub commands
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let count = client.subscribe(&["news"]).unwrap();
+    ///let message = client.next_message();
+    ///```
+    pub fn subscribe(&mut self, channels: &[&str]) -> Result<usize, RedisError> {
+        let count = self.change_subscriptions("SUBSCRIBE", channels)?;
+
+        usize::try_from(count).map_err(|_| RedisError::Protocol(format!("Negative subscription count {}", count)))
+    }
+
+    ///Set max count of channels sent in one subscription command, longer lists are sent in several commands, default is 1 000
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.set_subscribe_chunk_size(100);
+    ///```
+    pub fn set_subscribe_chunk_size(&mut self, size: usize) {
+        self.subscribe_chunk_size = size.max(1);
+    }
+
+    ///Subscribe to channels matching patterns
+    pub fn psubscribe(&mut self, patterns: &[&str]) -> Result<(), RedisError> {
+        self.change_subscriptions("PSUBSCRIBE", patterns).map(|_| ())
+    }
+
+    ///Subscribe to shard channels, in cluster connection has to go to node owning channel slot
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.ssubscribe(&["orders:{eu}"]).unwrap();
+    ///let message = client.next_message();
+    ///```
+    pub fn ssubscribe(&mut self, channels: &[&str]) -> Result<(), RedisError> {
+        self.change_subscriptions("SSUBSCRIBE", channels).map(|_| ())
+    }
+
+    ///Unsubscribe from shard channels
+    pub fn sunsubscribe(&mut self, channels: &[&str]) -> Result<(), RedisError> {
+        self.change_subscriptions("SUNSUBSCRIBE", channels).map(|_| ())
+    }
+
+    ///Unsubscribe from channels
+    pub fn unsubscribe(&mut self, channels: &[&str]) -> Result<(), RedisError> {
+        self.change_subscriptions("UNSUBSCRIBE", channels).map(|_| ())
+    }
+
+    ///Unsubscribe from all channels, client leaves subscriber mode unless patterns are subscribed
+    ///
+    ///Server confirms every channel separately, confirmations are read until count of subscriptions drops
+    ///to count of subscribed patterns
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.subscribe(&["news", "weather"]).unwrap();
+    ///client.unsubscribe_all().unwrap();
+    ///```
+    pub fn unsubscribe_all(&mut self) -> Result<(), RedisError> {
+        self.write(&Cmd::create_command(&Cmd::cmd("UNSUBSCRIBE").args))?;
+
+        let remaining = self.pubsub.patterns.len() as i64;
+        loop {
+            match Frame::from_values(self.read_frame()?)? {
+                Frame::Message(message) => self.pubsub.pending.push_back(message),
+                Frame::Subscription { sharded, count } => {
+                    self.pubsub.set_count(sharded, count);
+                    if !sharded && count <= remaining {
+                        break;
+                    }
+                },
+                Frame::Pong => {},
+            }
+        }
+        self.pubsub.channels.clear();
+
+        Ok(())
+    }
+
+    ///Unsubscribe from patterns
+    pub fn punsubscribe(&mut self, patterns: &[&str]) -> Result<(), RedisError> {
+        self.change_subscriptions("PUNSUBSCRIBE", patterns).map(|_| ())
+    }
+
+    ///Publish message to channel, returns count of clients received it
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let receivers = client.publish("news", "hello").unwrap();
+    ///```
+    pub fn publish(&mut self, channel: &str, message: &str) -> Result<i64, RedisError> {
+        self.publish_bytes(channel, message.as_bytes())
+    }
+
+    ///Publish binary message to channel, returns count of clients received it
+    pub fn publish_bytes(&mut self, channel: &str, message: &[u8]) -> Result<i64, RedisError> {
+        i64::from_redis_value(Cmd::cmd("PUBLISH").arg(channel).arg_bytes(message).execute(self)?)
+    }
+
+    ///Publish message to shard channel, returns count of clients received it
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let receivers = client.spublish("orders:{eu}", "created").unwrap();
+    ///```
+    pub fn spublish(&mut self, shardchannel: &str, message: &str) -> Result<i64, RedisError> {
+        i64::from_redis_value(Cmd::cmd("SPUBLISH").arg(shardchannel).arg(message).execute(self)?)
+    }
+
+    ///Block until next message of subscribed channels arrives
+    pub fn next_message(&mut self) -> Result<PubSubMessage, RedisError> {
+        if let Some(message) = self.pubsub.pending.pop_front() {
+            return Ok(message);
+        }
+
+        loop {
+            match Frame::from_values(self.read_frame()?)? {
+                Frame::Message(message) => return Ok(message),
+                Frame::Subscription { sharded, count } => self.pubsub.set_count(sharded, count),
+                Frame::Pong => {},
+            }
+        }
+    }
+
+    ///Block until next message arrives, `PING` is sent after `interval` of silence
+    ///and connection not answering it within another `interval` fails with `ErrorKind::TimedOut`
+    fn next_live_message(&mut self, interval: Duration) -> Result<PubSubMessage, RedisError> {
+        if let Some(message) = self.pubsub.pending.pop_front() {
+            return Ok(message);
+        }
+
+        self.connect.get_ref().set_read_timeout(Some(interval))?;
+        let mut awaiting_pong = false;
+        loop {
+            match self.read_frame() {
+                Ok(frame) => match Frame::from_values(frame)? {
+                    Frame::Message(message) => return Ok(message),
+                    Frame::Subscription { sharded, count } => self.pubsub.set_count(sharded, count),
+                    Frame::Pong => awaiting_pong = false,
+                },
+                Err(e) if matches!(e.io_error(), Some(io) if !is_disconnect(io)) => {
+                    if awaiting_pong {
+                        return Err(RedisError::Io(io::Error::new(ErrorKind::TimedOut, "Keepalive PING isn't answered")));
+                    }
+
+                    self.write(&Cmd::create_command(&Cmd::cmd("PING").args))?;
+                    awaiting_pong = true;
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    ///Send subscription commands of at most `subscribe_chunk_size` targets and wait for confirmation of every target,
+    ///returns count of last confirmation
+    fn change_subscriptions(&mut self, command: &str, targets: &[&str]) -> Result<i64, RedisError> {
+        if targets.is_empty() {
+            return Err(RedisError::InvalidArgument(format!("{} requires at least one channel", command)));
+        }
+
+        let mut last_count = 0;
+        for chunk in targets.chunks(self.subscribe_chunk_size) {
+            self.write(&Cmd::create_command(&Cmd::cmd(command).args(chunk).args))?;
+
+            let mut confirmed = 0;
+            while confirmed < chunk.len() {
+                match Frame::from_values(self.read_frame()?)? {
+                    Frame::Message(message) => self.pubsub.pending.push_back(message),
+                    Frame::Subscription { sharded, count } => {
+                        self.pubsub.set_count(sharded, count);
+                        last_count = count;
+                        confirmed += 1;
+                    },
+                    Frame::Pong => {},
+                }
+            }
+        }
+
+        let targets = targets.iter().map(|target| target.to_string());
+        match command {
+            "SUBSCRIBE" => self.pubsub.channels.extend(targets),
+            "PSUBSCRIBE" => self.pubsub.patterns.extend(targets),
+            "SSUBSCRIBE" => self.pubsub.shard_channels.extend(targets),
+            "UNSUBSCRIBE" => targets.for_each(|channel| { self.pubsub.channels.remove(&channel); }),
+            "SUNSUBSCRIBE" => targets.for_each(|channel| { self.pubsub.shard_channels.remove(&channel); }),
+            _ => targets.for_each(|pattern| { self.pubsub.patterns.remove(&pattern); }),
+        }
+
+        Ok(last_count)
+    }
+
+    ///Reconnect and subscribe again to all channels and patterns of lost connection
+    fn resubscribe(&mut self) -> Result<(), RedisError> {
+        let channels: Vec<String> = self.pubsub.channels.iter().cloned().collect();
+        let patterns: Vec<String> = self.pubsub.patterns.iter().cloned().collect();
+        let shard_channels: Vec<String> = self.pubsub.shard_channels.iter().cloned().collect();
+
+        self.reconnect()?;
+        if !channels.is_empty() {
+            self.subscribe(&channels.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        if !patterns.is_empty() {
+            self.psubscribe(&patterns.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        if !shard_channels.is_empty() {
+            self.ssubscribe(&shard_channels.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+
+        Ok(())
+    }
+
+    ///Subscribe to channels and turn client into message consumer
+    ///```no_run
+    ///use redis_client::Client;
+    ///let client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let subscriber = client.into_subscriber(&["news", "weather"]);
+    ///```
+    pub fn into_subscriber(self, channels: &[&str]) -> Result<Subscriber, RedisError> {
+        Subscriber::with_capacity(self, channels, DEFAULT_SUBSCRIBER_CAPACITY)
+    }
+
+    ///Subscribe to keyspace notifications of keys of database `db` matching `pattern`
+    ///
+    ///Server publishes them only when enabled by `enable_keyspace_events` or its configuration
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.enable_keyspace_events("KA").unwrap();
+    ///let subscriber = client.watch_keyspace(0, "user:*").unwrap();
+    ///```
+    pub fn watch_keyspace(mut self, db: u8, pattern: &str) -> Result<Subscriber, RedisError> {
+        self.psubscribe(&[&format!("__keyspace@{}__:{}", db, pattern)])?;
+
+        Subscriber::start(self, DEFAULT_SUBSCRIBER_CAPACITY, None)
+    }
+
+    ///Enable keyspace notifications of server by `CONFIG SET notify-keyspace-events`,
+    ///`flags` select event classes like `KA` for keyspace channels of all events
+    pub fn enable_keyspace_events(&mut self, flags: &str) -> Result<(), RedisError> {
+        match Cmd::cmd("CONFIG").arg("SET").arg("notify-keyspace-events").arg(flags).run(self)? {
+            Values::Errors(e) => Err(RedisError::server(e)),
+            value if value.is_ok() => Ok(()),
+            other => Err(RedisError::unexpected_reply("OK", &other)),
+        }
+    }
+
+    ///Read messages into channel until subscriber is dropped or connection can't be restored
+    fn deliver_messages(
+        mut self,
+        sender: SyncSender<Result<PubSubMessage, RedisError>>,
+        stream: &Mutex<Transport>,
+        closed: &AtomicBool,
+        keepalive: Option<Duration>,
+    ) {
+        loop {
+            let message = match keepalive {
+                Some(interval) => self.next_live_message(interval),
+                None => self.next_message(),
+            };
+            let result = match message {
+                Err(e) if e.io_error().is_some() && !closed.load(Ordering::SeqCst) => {
+                    self.restore_subscriptions(stream, closed).map(|_| PubSubMessage::Reconnected).map_err(|_| e)
+                },
+                result => result,
+            };
+
+            if closed.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let failed = result.is_err();
+            if sender.send(result).is_err() || failed {
+                break;
+            }
+        }
+    }
+
+    fn restore_subscriptions(&mut self, stream: &Mutex<Transport>, closed: &AtomicBool) -> Result<(), RedisError> {
+        let mut delay = RECONNECT_DELAY;
+        let mut attempt = 1;
+
+        loop {
+            match self.resubscribe() {
+                Ok(()) => break,
+                Err(e) if attempt >= RECONNECT_ATTEMPTS => return Err(e),
+                Err(_) => {
+                    self.clock.sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                },
+            }
+        }
+
+        let mut stream = stream.lock().map_err(|_| RedisError::Protocol(String::from("Subscriber lock poisoned")))?;
+        if closed.load(Ordering::SeqCst) {
+            let _ = self.connect.get_ref().shutdown();
+        } else {
+            *stream = self.connect.get_ref().try_clone()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use super::Subscriber;
+    use crate::{Client, Cmd, KeyspaceEvent, OtherChannels, PubSubMessage, RedisError, Values};
+
+    #[test]
+    fn skip_confirmation_and_receive_message() {
+        let server = MockServer::new(&[
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n",
+        ]);
+        let subscriber = server.client().into_subscriber(&["news"]).unwrap();
+
+        assert_eq!(
+            PubSubMessage::Message {
+                channel: String::from("news"),
+                payload: String::from("hello"),
+                payload_bytes: b"hello".to_vec(),
+            },
+            subscriber.recv().unwrap()
+        );
+        assert!(subscriber.recv().is_err());
+        assert_eq!(vec![vec!["SUBSCRIBE", "news"]], server.commands());
+    }
+
+    fn message(channel: &str, payload: &str) -> PubSubMessage {
+        PubSubMessage::Message {
+            channel: channel.to_string(),
+            payload: payload.to_string(),
+            payload_bytes: payload.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn recv_from_requested_channel() {
+        let server = MockServer::new(&[
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$7\r\nweather\r\n:2\r\n\
+            *3\r\n$7\r\nmessage\r\n$7\r\nweather\r\n$5\r\nsunny\r\n*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$3\r\none\r\n\
+            *3\r\n$7\r\nmessage\r\n$7\r\nweather\r\n$5\r\nrainy\r\n*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$3\r\ntwo\r\n",
+        ]);
+        let mut subscriber = server.client().into_subscriber(&["news", "weather"]).unwrap();
+
+        assert_eq!(message("news", "one"), subscriber.recv_from("news").unwrap());
+        subscriber.set_other_channels(OtherChannels::Drop);
+        assert_eq!(message("news", "two"), subscriber.recv_from("news").unwrap());
+        assert_eq!(message("weather", "sunny"), subscriber.recv().unwrap());
+        assert!(subscriber.recv().is_err());
+    }
+
+    #[test]
+    fn resubscribe_after_connection_drop() {
+        let server = MockServer::sessions(&[
+            &["*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$3\r\none\r\n"],
+            &["*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$3\r\ntwo\r\n"],
+        ]);
+        let subscriber = server.client().into_subscriber(&["news"]).unwrap();
+
+        assert_eq!(
+            PubSubMessage::Message {
+                channel: String::from("news"),
+                payload: String::from("one"),
+                payload_bytes: b"one".to_vec(),
+            },
+            subscriber.recv().unwrap()
+        );
+        assert_eq!(PubSubMessage::Reconnected, subscriber.recv().unwrap());
+        assert_eq!(
+            PubSubMessage::Message {
+                channel: String::from("news"),
+                payload: String::from("two"),
+                payload_bytes: b"two".to_vec(),
+            },
+            subscriber.recv().unwrap()
+        );
+        assert_eq!(vec![vec!["SUBSCRIBE", "news"], vec!["SUBSCRIBE", "news"]], server.commands());
+    }
+
+    #[test]
+    fn full_queue_stops_reading_socket() {
+        use std::{io::{BufReader, ErrorKind, Write}, net::TcpListener, thread, time::{Duration, Instant}};
+
+        const CONFIRMATION: &str = "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            crate::parse_response(&mut reader).unwrap();
+            let messages: String = ["one", "two", "three"].iter()
+                .map(|payload| format!("*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n${}\r\n{}\r\n", payload.len(), payload))
+                .collect();
+            reader.get_mut().write_all(format!("{}{}", CONFIRMATION, messages).as_bytes()).unwrap();
+
+            listener
+        });
+
+        let subscriber = Subscriber::with_capacity(Client::new(addr).unwrap(), &["news"], 1).unwrap();
+        let listener = server.join().unwrap();
+        listener.set_nonblocking(true).unwrap();
+        thread::sleep(Duration::from_millis(200));
+
+        //Reader waits for free slot, so it neither reached end of closed connection nor reconnected
+        assert_eq!(ErrorKind::WouldBlock, listener.accept().unwrap_err().kind());
+        for payload in ["one", "two", "three"].iter() {
+            assert_eq!(message("news", payload), subscriber.recv().unwrap());
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(e) if e.kind() == ErrorKind::WouldBlock && Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(10))
+                },
+                Err(e) => panic!("Reader didn't reconnect: {}", e),
+            }
+        };
+        stream.set_nonblocking(false).unwrap();
+        let mut reader = BufReader::new(stream);
+        crate::parse_response(&mut reader).unwrap();
+        reader.get_mut().write_all(CONFIRMATION.as_bytes()).unwrap();
+        assert_eq!(PubSubMessage::Reconnected, subscriber.recv().unwrap());
+    }
+
+    #[test]
+    fn keepalive_detects_silent_connection() {
+        use std::{io::{BufReader, Write}, net::TcpListener, thread, time::Duration};
+
+        const CONFIRMATION: &str = "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n";
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let mut commands = Vec::new();
+            let (stream, _) = listener.accept().unwrap();
+            let mut silent = BufReader::new(stream);
+            commands.push(crate::parse_response(&mut silent).unwrap());
+            silent.get_mut().write_all(CONFIRMATION.as_bytes()).unwrap();
+            commands.push(crate::parse_response(&mut silent).unwrap());
+            silent.get_mut().write_all(b"*2\r\n$4\r\npong\r\n$0\r\n\r\n").unwrap();
+            //Second PING is never answered while connection stays open
+            commands.push(crate::parse_response(&mut silent).unwrap());
+
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            commands.push(crate::parse_response(&mut reader).unwrap());
+            let message = "*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n";
+            reader.get_mut().write_all(format!("{}{}", CONFIRMATION, message).as_bytes()).unwrap();
+
+            (commands, silent)
+        });
+
+        let client = Client::new(addr).unwrap();
+        let subscriber = Subscriber::with_keepalive(client, &["news"], Duration::from_millis(50)).unwrap();
+
+        assert_eq!(PubSubMessage::Reconnected, subscriber.recv().unwrap());
+        assert_eq!(message("news", "hello"), subscriber.recv().unwrap());
+        let (commands, _silent) = server.join().unwrap();
+        let command = |args: &[&str]| Values::Arrays(args.iter().map(|arg| Values::BulkString(arg.to_string())).collect());
+        assert_eq!(
+            vec![command(&["SUBSCRIBE", "news"]), command(&["PING"]), command(&["PING"]), command(&["SUBSCRIBE", "news"])],
+            commands
+        );
+    }
+
+    #[test]
+    fn reconnect_delay_doubled() {
+        use std::{sync::{atomic::AtomicBool, Mutex}, time::{Duration, Instant}};
+
+        let server = MockServer::new(&[]);
+        let mut client = server.client();
+        server.commands();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+        let stream = Mutex::new(client.connect.get_ref().try_clone().unwrap());
+
+        let started = Instant::now();
+        assert!(client.restore_subscriptions(&stream, &AtomicBool::new(false)).is_err());
+        assert_eq!(vec![Duration::from_millis(100), Duration::from_millis(200)], clock.sleeps());
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn parse_keyspace_notifications() {
+        let keyspace = PubSubMessage::PMessage {
+            pattern: String::from("__keyspace@0__:user:*"),
+            channel: String::from("__keyspace@0__:user:1"),
+            payload: String::from("expired"),
+            payload_bytes: b"expired".to_vec(),
+        };
+        let keyevent = message("__keyevent@3__:del", "user:2");
+
+        assert_eq!(
+            Some(KeyspaceEvent { key: String::from("user:1"), event: String::from("expired") }),
+            KeyspaceEvent::from_message(&keyspace)
+        );
+        assert_eq!(
+            Some(KeyspaceEvent { key: String::from("user:2"), event: String::from("del") }),
+            KeyspaceEvent::from_message(&keyevent)
+        );
+        assert_eq!(None, KeyspaceEvent::from_message(&message("news", "hello")));
+        assert_eq!(None, KeyspaceEvent::from_message(&message("__keyspace@x__:user:1", "del")));
+        assert_eq!(None, KeyspaceEvent::from_message(&PubSubMessage::Reconnected));
+    }
+
+    #[test]
+    fn watch_keyspace_psubscribes() {
+        let server = MockServer::new(&[concat!(
+            "*3\r\n$10\r\npsubscribe\r\n$21\r\n__keyspace@0__:user:*\r\n:1\r\n",
+            "*4\r\n$8\r\npmessage\r\n$21\r\n__keyspace@0__:user:*\r\n$21\r\n__keyspace@0__:user:1\r\n$3\r\nset\r\n",
+        )]);
+        let subscriber = server.client().watch_keyspace(0, "user:*").unwrap();
+
+        let event = subscriber.recv_keyspace().unwrap();
+        assert_eq!(KeyspaceEvent { key: String::from("user:1"), event: String::from("set") }, event);
+        assert!(subscriber.recv_keyspace().is_err());
+        assert_eq!(vec![vec!["PSUBSCRIBE", "__keyspace@0__:user:*"]], server.commands());
+    }
+
+    #[test]
+    fn subscribe_returns_last_count() {
+        let server = MockServer::new(&[
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$7\r\nweather\r\n:2\r\n",
+        ]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.subscribe(&["news", "weather"]).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["SUBSCRIBE", "news", "weather"]], server.commands());
+    }
+
+    #[test]
+    fn subscribe_in_chunks() {
+        let channels: Vec<String> = (0..250).map(|i| format!("channel:{}", i)).collect();
+        let replies: Vec<String> = channels.chunks(100)
+            .scan(0, |count, chunk| {
+                Some(chunk.iter().map(|channel| {
+                    *count += 1;
+                    format!("*3\r\n$9\r\nsubscribe\r\n${}\r\n{}\r\n:{}\r\n", channel.len(), channel, count)
+                }).collect())
+            })
+            .collect();
+        let server = MockServer::new(&replies.iter().map(String::as_str).collect::<Vec<_>>());
+        let mut client = server.client();
+        client.set_subscribe_chunk_size(100);
+
+        assert_eq!(250, client.subscribe(&channels.iter().map(String::as_str).collect::<Vec<_>>()).unwrap());
+        drop(client);
+        let commands = server.commands();
+        assert_eq!(3, commands.len());
+        assert!(commands.iter().all(|command| command[0] == "SUBSCRIBE"));
+        assert_eq!(vec![101, 101, 51], commands.iter().map(Vec::len).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn unsubscribe_all_reads_every_confirmation() {
+        let server = MockServer::new(&[
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$7\r\nweather\r\n:2\r\n",
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$11\r\nunsubscribe\r\n$7\r\nweather\r\n:0\r\n",
+            "$5\r\nvalue\r\n",
+        ]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.subscribe(&["news", "weather"]).unwrap());
+        client.unsubscribe_all().unwrap();
+        assert!(!client.pubsub.is_active());
+        assert_eq!(Values::BulkString(String::from("value")), client.command(Cmd::cmd("GET").arg("key")).unwrap());
+        drop(client);
+        assert_eq!(vec!["UNSUBSCRIBE"], server.commands()[1]);
+    }
+
+    #[test]
+    fn unsubscribe_all_without_subscriptions() {
+        let server = MockServer::new(&["*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n"]);
+        let mut client = server.client();
+
+        client.unsubscribe_all().unwrap();
+        assert!(!client.pubsub.is_active());
+    }
+
+    #[test]
+    fn guard_commands_in_subscriber_mode() {
+        let server = MockServer::new(&[
+            "*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n",
+            "*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:0\r\n",
+            "$5\r\nvalue\r\n",
+        ]);
+        let mut client = server.client();
+
+        client.subscribe(&["news"]).unwrap();
+        match Cmd::cmd("GET").arg("key").execute(&mut client) {
+            Err(RedisError::InSubscriberMode { allowed }) => {
+                assert!(allowed.contains(&"SUBSCRIBE"));
+                assert!(allowed.contains(&"PING"));
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        client.unsubscribe(&["news"]).unwrap();
+        assert_eq!(Values::BulkString(String::from("value")), client.command(Cmd::cmd("GET").arg("key")).unwrap());
+    }
+
+    #[test]
+    fn publish_returns_receivers() {
+        let server = MockServer::new(&[":2\r\n", ":0\r\n"]);
+        let mut client = server.client();
+
+        assert_eq!(2, client.publish("news", "hello").unwrap());
+        assert_eq!(0, client.publish_bytes("raw", &[0xff, 0x01]).unwrap());
+        drop(client);
+        assert_eq!(vec![vec!["PUBLISH", "news", "hello"], vec!["PUBLISH", "raw", "\u{fffd}\u{1}"]], server.commands());
+    }
+
+    #[test]
+    fn receive_binary_payload() {
+        let mut reply = b"*3\r\n$9\r\nsubscribe\r\n$3\r\nraw\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$3\r\nraw\r\n$4\r\n".to_vec();
+        reply.extend_from_slice(&[0xff, 0x00, 0xc3, 0x28]);
+        reply.extend_from_slice(b"\r\n");
+        let server = MockServer::with_bytes(&[&reply]);
+        let subscriber = server.client().into_subscriber(&["raw"]).unwrap();
+
+        match subscriber.recv().unwrap() {
+            PubSubMessage::Message { channel, payload_bytes, .. } => {
+                assert_eq!("raw", channel);
+                assert_eq!(vec![0xff, 0x00, 0xc3, 0x28], payload_bytes);
+            },
+            other => panic!("Unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn receive_shard_message() {
+        let server = MockServer::new(&[
+            "*3\r\n$10\r\nssubscribe\r\n$6\r\norders\r\n:1\r\n",
+            "*3\r\n$12\r\nsunsubscribe\r\n$6\r\norders\r\n:0\r\n",
+            ":1\r\n",
+        ]);
+        let mut client = server.client();
+
+        client.ssubscribe(&["orders"]).unwrap();
+        assert!(matches!(Cmd::cmd("GET").arg("key").execute(&mut client), Err(RedisError::InSubscriberMode { .. })));
+
+        let mut push = b"*3\r\n$8\r\nsmessage\r\n$6\r\norders\r\n$7\r\ncreated\r\n".as_slice();
+        let frame = super::Frame::from_values(crate::parse_response(&mut push).unwrap()).unwrap();
+        match frame {
+            super::Frame::Message(message) => assert_eq!(
+                PubSubMessage::SMessage {
+                    channel: String::from("orders"),
+                    payload: String::from("created"),
+                    payload_bytes: b"created".to_vec(),
+                },
+                message
+            ),
+            _ => panic!("Expected message frame"),
+        }
+
+        client.sunsubscribe(&["orders"]).unwrap();
+        assert_eq!(1, client.spublish("orders", "shipped").unwrap());
+        drop(client);
+        assert_eq!(
+            vec![vec!["SSUBSCRIBE", "orders"], vec!["SUNSUBSCRIBE", "orders"], vec!["SPUBLISH", "orders", "shipped"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn receive_published_message() {
+        let subscriber = Client::new("127.0.0.1:6379").unwrap().into_subscriber(&["test_channel"]).unwrap();
+        let mut publisher = Client::new("127.0.0.1:6379").unwrap();
+
+        publisher.publish("test_channel", "hello").unwrap();
+
+        assert_eq!(
+            PubSubMessage::Message {
+                channel: String::from("test_channel"),
+                payload: String::from("hello"),
+                payload_bytes: b"hello".to_vec(),
+            },
+            subscriber.recv().unwrap()
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use std::net::TcpStream;
+
+use crate::{Client, Cmd, Origin, RedisError, Values};
+
+impl Client {
+    ///Ask sentinels for address of master and connect to it, sentinels are tried in order
+    ///```no_run
+    ///use redis_client::Client;
+    ///let client = Client::from_sentinels(&["10.0.0.1:26379", "10.0.0.2:26379"], "mymaster");
+    ///```
+    pub fn from_sentinels(sentinels: &[&str], master_name: &str) -> Result<Self, RedisError> {
+        let sentinels: Vec<String> = sentinels.iter().map(|s| s.to_string()).collect();
+        let stream = crate::TcpConnection::new(connect_master(&sentinels, master_name)?)?;
+
+        Ok(Client::with_origin(stream, Origin::Sentinels { sentinels, master_name: master_name.to_string() }))
+    }
+}
+
+///Resolve master by first answering sentinel and connect to it
+pub(crate) fn connect_master(sentinels: &[String], master_name: &str) -> Result<TcpStream, RedisError> {
+    let mut failures = Vec::new();
+
+    for sentinel in sentinels {
+        match master_addr(sentinel, master_name).and_then(|addr| Ok(TcpStream::connect(addr)?)) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => failures.push((sentinel.clone(), e)),
+        }
+    }
+
+    Err(RedisError::Connect(failures))
+}
+
+fn master_addr(sentinel: &str, master_name: &str) -> Result<(String, u16), RedisError> {
+    let mut client = Client::new(sentinel)?;
+    let reply = Cmd::cmd("SENTINEL").arg("get-master-addr-by-name").arg(master_name).execute(&mut client)?;
+
+    match reply {
+        Values::Arrays(parts) => match parts.as_slice() {
+            [Values::BulkString(host), Values::BulkString(port)] => Ok((host.clone(), port.parse()?)),
+            _ => Err(RedisError::Protocol(format!("Master address must be host and port, got {:?}", parts))),
+        },
+        Values::Nil => Err(RedisError::Server {
+            code: String::from("ERR"),
+            message: format!("Sentinel doesn't know master {}", master_name),
+        }),
+        Values::Errors(e) => Err(RedisError::server(e)),
+        other => Err(RedisError::unexpected_reply("array", &other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{Client, Cmd, Values};
+
+    fn master_reply(master: &MockServer) -> String {
+        let port = master.addr().port().to_string();
+
+        format!("*2\r\n$9\r\n127.0.0.1\r\n${}\r\n{}\r\n", port.len(), port)
+    }
+
+    #[test]
+    fn connect_to_resolved_master() {
+        let master = MockServer::new(&["+OK\r\n"]);
+        let sentinel = MockServer::new(&[&master_reply(&master)]);
+
+        let mut client = Client::from_sentinels(&[&sentinel.addr().to_string()], "mymaster").unwrap();
+
+        assert_eq!(
+            Values::SimpleString(String::from("OK")),
+            Cmd::cmd("SET").arg("key").arg("value").execute(&mut client).unwrap()
+        );
+        assert_eq!(vec![vec!["SENTINEL", "get-master-addr-by-name", "mymaster"]], sentinel.commands());
+        assert_eq!(vec![vec!["SET", "key", "value"]], master.commands());
+    }
+
+    #[test]
+    fn reconnect_queries_sentinels_again() {
+        let old_master = MockServer::new(&[]);
+        let new_master = MockServer::new(&["+OK\r\n"]);
+        let first_sentinel = MockServer::new(&[&master_reply(&old_master)]);
+        let second_sentinel = MockServer::new(&[&master_reply(&new_master)]);
+        let sentinels = [first_sentinel.addr().to_string(), second_sentinel.addr().to_string()];
+
+        let mut client = Client::from_sentinels(&[&sentinels[0], &sentinels[1]], "mymaster").unwrap();
+        first_sentinel.commands();
+        old_master.commands();
+        client.reconnect().unwrap();
+
+        Cmd::cmd("SET").arg("key").arg("value").execute(&mut client).unwrap();
+        assert_eq!(vec![vec!["SET", "key", "value"]], new_master.commands());
+    }
+}
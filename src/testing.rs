@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use crate::{Client, Cmd, FromRedisValue, RedisError, Values};
+
+///Delay between checks of `force_expire_now`
+const EXPIRE_CHECK_DELAY: Duration = Duration::from_millis(2);
+///How many times `force_expire_now` checks key is gone
+const EXPIRE_CHECKS: u32 = 5;
+
+///Check reply is `+OK`
+///```
+///use redis_client::{expect_ok, Values};
+///
+///assert!(expect_ok(&Values::SimpleString(String::from("OK"))).is_ok());
+///```
+pub fn expect_ok(v: &Values) -> Result<(), RedisError> {
+    if v.is_ok() {
+        Ok(())
+    } else {
+        Err(RedisError::AssertionFailed(format!("expected OK reply, got {:?}", v)))
+    }
+}
+
+///Check reply is integer `n`
+///```
+///use redis_client::{expect_int, Values};
+///
+///assert!(expect_int(&Values::Integers(2), 2).is_ok());
+///```
+pub fn expect_int(v: &Values, n: i64) -> Result<(), RedisError> {
+    match v {
+        Values::Integers(i) if *i == n => Ok(()),
+        other => Err(RedisError::AssertionFailed(format!("expected integer reply {}, got {:?}", n, other))),
+    }
+}
+
+///Check reply is bulk string `s`
+///```
+///use redis_client::{expect_bulk, Values};
+///
+///assert!(expect_bulk(&Values::BulkString(String::from("value")), "value").is_ok());
+///```
+pub fn expect_bulk(v: &Values, s: &str) -> Result<(), RedisError> {
+    match v {
+        Values::BulkString(bulk) if bulk == s => Ok(()),
+        other => Err(RedisError::AssertionFailed(format!("expected bulk string reply {:?}, got {:?}", s, other))),
+    }
+}
+
+impl Client {
+    ///Check internal encoding of key, for tests verifying value stays in compact encoding
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.assert_encoding("small_list", "listpack").unwrap();
+    ///```
+    pub fn assert_encoding(&mut self, key: &str, expected: &str) -> Result<(), RedisError> {
+        match self.object_encoding(key)? {
+            Some(ref actual) if actual == expected => Ok(()),
+            actual => Err(RedisError::AssertionFailed(format!(
+                "expected encoding {} of key {}, got {:?}",
+                expected, key, actual
+            ))),
+        }
+    }
+
+    ///Turn background expiry of server on or off, keys then expire only when accessed
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.debug_set_active_expire(false).unwrap();
+    ///```
+    pub fn debug_set_active_expire(&mut self, on: bool) -> Result<(), RedisError> {
+        let reply = self.debug("SET-ACTIVE-EXPIRE", &[if on { "1" } else { "0" }])?;
+        if reply.is_ok() {
+            Ok(())
+        } else {
+            Err(RedisError::unexpected_reply("OK", &reply))
+        }
+    }
+
+    ///Expire key right away and wait until server reports it's gone
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.force_expire_now("session").unwrap();
+    ///```
+    pub fn force_expire_now(&mut self, key: &str) -> Result<(), RedisError> {
+        if !bool::from_redis_value(Cmd::cmd("PEXPIRE").key(key).arg("1").run(self)?)? {
+            return Err(RedisError::AssertionFailed(format!("key {} doesn't exist", key)));
+        }
+
+        for _ in 0..EXPIRE_CHECKS {
+            self.clock.sleep(EXPIRE_CHECK_DELAY);
+            if !bool::from_redis_value(Cmd::cmd("EXISTS").key(key).run(self)?)? {
+                return Ok(());
+            }
+        }
+
+        Err(RedisError::AssertionFailed(format!("key {} didn't expire", key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expect_bulk, expect_int, expect_ok};
+    use crate::mock::MockServer;
+    use crate::{Client, Cmd, RedisError, Values};
+
+    fn failure_message(result: Result<(), RedisError>) -> String {
+        match result {
+            Err(RedisError::AssertionFailed(message)) => message,
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expect_ok_reply() {
+        expect_ok(&Values::SimpleString(String::from("OK"))).unwrap();
+
+        assert_eq!(
+            "expected OK reply, got Errors(\"ERR syntax error\")",
+            failure_message(expect_ok(&Values::Errors(String::from("ERR syntax error"))))
+        );
+    }
+
+    #[test]
+    fn expect_int_reply() {
+        expect_int(&Values::Integers(3), 3).unwrap();
+
+        assert_eq!("expected integer reply 3, got Integers(4)", failure_message(expect_int(&Values::Integers(4), 3)));
+        assert_eq!("expected integer reply 3, got Nil", failure_message(expect_int(&Values::Nil, 3)));
+    }
+
+    #[test]
+    fn expect_bulk_reply() {
+        expect_bulk(&Values::BulkString(String::from("value")), "value").unwrap();
+
+        assert_eq!(
+            "expected bulk string reply \"value\", got SimpleString(\"value\")",
+            failure_message(expect_bulk(&Values::SimpleString(String::from("value")), "value"))
+        );
+    }
+
+    #[test]
+    fn force_expire_set_key() {
+        let server = MockServer::new(&["+OK\r\n", "+OK\r\n", ":1\r\n", ":1\r\n", ":0\r\n", "$-1\r\n"]);
+        let mut client = server.client();
+        let clock = crate::clock::MockClock::new();
+        client.clock = clock.clone();
+
+        client.debug_set_active_expire(false).unwrap();
+        client.set("key", "value").unwrap();
+        client.force_expire_now("key").unwrap();
+        assert_eq!(None, client.get("key").unwrap());
+        assert_eq!(vec![super::EXPIRE_CHECK_DELAY; 2], clock.sleeps());
+        drop(client);
+        assert_eq!(
+            vec![
+                vec!["DEBUG", "SET-ACTIVE-EXPIRE", "0"],
+                vec!["SET", "key", "value"],
+                vec!["PEXPIRE", "key", "1"],
+                vec!["EXISTS", "key"],
+                vec!["EXISTS", "key"],
+                vec!["GET", "key"],
+            ],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn force_expire_missing_key() {
+        let server = MockServer::new(&[":0\r\n"]);
+        let mut client = server.client();
+
+        assert!(matches!(client.force_expire_now("missing"), Err(RedisError::AssertionFailed(_))));
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn small_list_is_listpack() {
+        let mut client = Client::new("127.0.0.1:6379").unwrap();
+        Cmd::cmd("DEL").arg("test_small_list").execute(&mut client).unwrap();
+        Cmd::cmd("RPUSH").arg("test_small_list").arg("a").arg("b").execute(&mut client).unwrap();
+
+        client.assert_encoding("test_small_list", "listpack").unwrap();
+        assert!(client.assert_encoding("test_small_list", "quicklist").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{Client, Cmd, FromRedisValue, RedisError, Values};
+
+///Attempts of `Client::update` before giving up on concurrent changes
+const UPDATE_ATTEMPTS: usize = 16;
+
+///Commands executed atomically between `MULTI` and `EXEC`
+#[derive(Debug, Default)]
+pub struct Transaction {
+    commands: Vec<Cmd>,
+}
+
+impl Transaction {
+    ///Start create transaction
+    ///```
+    ///use redis_client::{Cmd, Transaction};
+    ///
+    ///let transaction = Transaction::new()
+    ///    .command(Cmd::cmd("INCR").arg("first"))
+    ///    .command(Cmd::cmd("INCR").arg("second"));
+    ///```
+    pub fn new() -> Self {
+        Transaction::default()
+    }
+
+    ///Add command to transaction
+    pub fn command(mut self, cmd: Cmd) -> Self {
+        self.commands.push(cmd);
+
+        self
+    }
+
+    ///Execute transaction and return replies of commands from `EXEC`
+    ///
+    ///Command rejected while queueing aborts whole transaction, its error is returned with index of command
+    ///```no_run
+    ///use redis_client::{Cmd, Client, Transaction};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let replies = Transaction::new()
+    ///    .command(Cmd::cmd("INCR").arg("counter"))
+    ///    .command(Cmd::cmd("EXPIRE").arg("counter").arg("60"))
+    ///    .execute(&mut client);
+    ///```
+    pub fn execute(self, conn: &mut Client) -> Result<Vec<Values>, RedisError> {
+        for cmd in &self.commands {
+            cmd.validate(conn)?;
+        }
+
+        let mut buff = Cmd::create_command(&Cmd::cmd("MULTI").args);
+        for cmd in &self.commands {
+            buff.extend_from_slice(&cmd.encode(conn));
+        }
+        buff.extend_from_slice(&Cmd::create_command(&Cmd::cmd("EXEC").args));
+        conn.write(&buff)?;
+
+        let multi = conn.read_pending_reply()?;
+        let mut queue_error = None;
+        for index in 0..self.commands.len() {
+            if let Values::Errors(e) = conn.read_pending_reply()? {
+                queue_error.get_or_insert(RedisError::AtIndex { index, error: Box::new(RedisError::server(e)) });
+            }
+        }
+        let exec = conn.read_pending_reply()?;
+
+        if let Values::Errors(e) = multi {
+            return Err(RedisError::server(e));
+        }
+        if let Some(e) = queue_error {
+            return Err(e);
+        }
+
+        match exec {
+            Values::Arrays(replies) => Ok(replies),
+            Values::Nil => Err(RedisError::TransactionAborted),
+            Values::Errors(e) => Err(RedisError::server(e)),
+            other => Err(RedisError::unexpected_reply("array", &other)),
+        }
+    }
+
+    ///Execute transaction and convert every reply, first failed conversion is returned with index of command
+    ///```no_run
+    ///use redis_client::{Cmd, Client, Transaction};
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///let counters: Vec<i64> = Transaction::new()
+    ///    .command(Cmd::cmd("INCR").arg("first"))
+    ///    .command(Cmd::cmd("INCR").arg("second"))
+    ///    .execute_typed(&mut client)
+    ///    .unwrap();
+    ///```
+    pub fn execute_typed<T: FromRedisValue>(self, conn: &mut Client) -> Result<Vec<T>, RedisError> {
+        self.execute(conn)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| T::from_redis_value(value)
+                .map_err(|error| RedisError::AtIndex { index, error: Box::new(error) }))
+            .collect()
+    }
+}
+
+impl Client {
+    ///Optimistically update string value of key with `WATCH`, `GET` and `MULTI`/`SET`/`EXEC`
+    ///
+    ///Closure receives current value and returns new one, `None` deletes key.
+    ///Whole loop is repeated when key is changed concurrently, `TransactionAborted` is returned when every attempt aborted
+    ///```no_run
+    ///use redis_client::Client;
+    ///let mut client = Client::new("127.0.0.1:6379").unwrap();
+    ///
+    ///client.update("counter", |value| {
+    ///    let counter = value.and_then(|value| value.parse::<i64>().ok()).unwrap_or(0);
+    ///    Some((counter + 1).to_string())
+    ///}).unwrap();
+    ///```
+    pub fn update<F>(&mut self, key: &str, mut f: F) -> Result<(), RedisError>
+    where
+        F: FnMut(Option<String>) -> Option<String>,
+    {
+        for _ in 0..UPDATE_ATTEMPTS {
+            if let Values::Errors(e) = Cmd::cmd("WATCH").key(key).run(self)? {
+                return Err(RedisError::server(e));
+            }
+
+            let current = match self.get(key) {
+                Ok(current) => current,
+                Err(e) => {
+                    let _ = Cmd::cmd("UNWATCH").run(self);
+
+                    return Err(e);
+                },
+            };
+
+            let cmd = match f(current) {
+                Some(value) => Cmd::cmd("SET").key(key).arg(&value),
+                None => Cmd::cmd("DEL").key(key),
+            };
+            match Transaction::new().command(cmd).execute(self) {
+                Ok(_) => return Ok(()),
+                Err(RedisError::TransactionAborted) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(RedisError::TransactionAborted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockServer;
+    use crate::{Cmd, RedisError, Transaction};
+
+    #[test]
+    fn typed_transaction_results() {
+        let server = MockServer::new(&["+OK\r\n", "+QUEUED\r\n", "+QUEUED\r\n", "*2\r\n:1\r\n:5\r\n"]);
+        let mut client = server.client();
+
+        let counters: Vec<i64> = Transaction::new()
+            .command(Cmd::cmd("INCR").arg("first"))
+            .command(Cmd::cmd("INCR").arg("second"))
+            .execute_typed(&mut client)
+            .unwrap();
+
+        assert_eq!(vec![1, 5], counters);
+        drop(client);
+        assert_eq!(
+            vec![vec!["MULTI"], vec!["INCR", "first"], vec!["INCR", "second"], vec!["EXEC"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    fn conversion_error_has_index() {
+        let server = MockServer::new(&["+OK\r\n", "+QUEUED\r\n", "+QUEUED\r\n", "*2\r\n:1\r\n-WRONGTYPE Operation\r\n"]);
+        let mut client = server.client();
+
+        let result = Transaction::new()
+            .command(Cmd::cmd("INCR").arg("counter"))
+            .command(Cmd::cmd("INCR").arg("name"))
+            .execute_typed::<i64>(&mut client);
+
+        match result {
+            Err(RedisError::AtIndex { index, error }) => {
+                assert_eq!(1, index);
+                assert!(matches!(*error, RedisError::Server { .. }));
+            },
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn aborted_by_watch() {
+        let server = MockServer::new(&["+OK\r\n", "+QUEUED\r\n", "*-1\r\n"]);
+        let mut client = server.client();
+
+        let result = Transaction::new().command(Cmd::cmd("INCR").arg("counter")).execute(&mut client);
+
+        assert!(matches!(result, Err(RedisError::TransactionAborted)));
+    }
+
+    #[test]
+    fn update_retried_after_concurrent_change() {
+        let server = MockServer::new(&[
+            "+OK\r\n", "$1\r\n1\r\n", "+OK\r\n", "+QUEUED\r\n", "*-1\r\n",
+            "+OK\r\n", "$1\r\n5\r\n", "+OK\r\n", "+QUEUED\r\n", "*1\r\n+OK\r\n",
+        ]);
+        let mut client = server.client();
+        let mut seen = Vec::new();
+
+        client.update("counter", |value| {
+            seen.push(value.clone());
+            value.map(|value| (value.parse::<i64>().unwrap() + 1).to_string())
+        }).unwrap();
+
+        assert_eq!(vec![Some(String::from("1")), Some(String::from("5"))], seen);
+        drop(client);
+        let attempt = |value: &'static str| vec![
+            vec!["WATCH", "counter"], vec!["GET", "counter"], vec!["MULTI"], vec!["SET", "counter", value], vec!["EXEC"],
+        ];
+        assert_eq!(attempt("2").into_iter().chain(attempt("6")).collect::<Vec<_>>(), server.commands());
+    }
+
+    #[test]
+    fn update_deletes_key_on_none() {
+        let server = MockServer::new(&["+OK\r\n", "$5\r\nvalue\r\n", "+OK\r\n", "+QUEUED\r\n", "*1\r\n:1\r\n"]);
+        let mut client = server.client();
+
+        client.update("key", |_| None).unwrap();
+
+        drop(client);
+        assert_eq!(
+            vec![vec!["WATCH", "key"], vec!["GET", "key"], vec!["MULTI"], vec!["DEL", "key"], vec!["EXEC"]],
+            server.commands()
+        );
+    }
+
+    #[test]
+    #[ignore = "requires a running redis server"]
+    fn racing_updates_all_applied() {
+        let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+        Cmd::cmd("DEL").arg("update_race").execute(&mut client).unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                scope.spawn(|| {
+                    let mut client = crate::Client::new("127.0.0.1:6379").unwrap();
+                    for _ in 0..50 {
+                        client.update("update_race", |value| {
+                            let counter = value.map_or(0, |value| value.parse::<i64>().unwrap());
+                            Some((counter + 1).to_string())
+                        }).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(Some(String::from("100")), client.get("update_race").unwrap());
+    }
+
+    #[test]
+    fn queued_replies_after_timeout_not_taken_by_next_command() {
+        let delay = std::time::Duration::from_millis(100);
+        let (addr, server) = crate::mock::slow_then_fast(delay, "+OK\r\n", "$3\r\nnew\r\n");
+        let mut client = crate::Client::new(addr).unwrap();
+        client.connect.get_ref().set_read_timeout(Some(std::time::Duration::from_millis(50))).unwrap();
+
+        let result = Transaction::new().command(Cmd::cmd("INCR").arg("slow")).execute(&mut client);
+        assert!(matches!(result, Err(RedisError::Io(_))));
+        assert_eq!(Some(String::from("new")), client.get("fast").unwrap());
+
+        assert_eq!(vec![vec!["MULTI"], vec!["GET", "fast"]], server.join().unwrap());
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use crate::{RedisError, Values};
+
+///Conversion of parsed reply into rust type
+///```
+///use redis_client::{FromRedisValue, HashFields, RedisError, Values};
+///
+///struct User {
+///    name: String,
+///    age: Option<i64>,
+///}
+///
+///impl FromRedisValue for User {
+///    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+///        let mut fields = HashFields::from_redis_value(value)?;
+///
+///        Ok(User { name: fields.take("name")?, age: fields.take("age")? })
+///    }
+///}
+///```
+pub trait FromRedisValue: Sized {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError>;
+}
+
+fn unexpected<T>(expected: &'static str, value: Values) -> Result<T, RedisError> {
+    match value {
+        Values::Errors(e) => Err(RedisError::server(e)),
+        other => Err(RedisError::unexpected_reply(expected, &other)),
+    }
+}
+
+impl FromRedisValue for Values {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        Ok(value)
+    }
+}
+
+impl FromRedisValue for String {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::BulkString(s) | Values::SimpleString(s) => Ok(s),
+            Values::Integers(i) => Ok(i.to_string()),
+            Values::BulkBytes(bytes) => Err(RedisError::Protocol(format!("Reply isn't valid UTF-8: {:?}", bytes))),
+            other => unexpected("string", other),
+        }
+    }
+}
+
+impl FromRedisValue for i64 {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::Integers(i) => Ok(i),
+            Values::BulkString(s) | Values::SimpleString(s) => Ok(s.parse()?),
+            other => unexpected("integer", other),
+        }
+    }
+}
+
+impl FromRedisValue for f64 {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::Double(d) => Ok(d),
+            Values::Integers(i) => Ok(i as f64),
+            Values::BulkString(s) | Values::SimpleString(s) => s
+                .parse()
+                .map_err(|_| RedisError::Protocol(format!("invalid float: {}", s))),
+            other => unexpected("float", other),
+        }
+    }
+}
+
+///Integer of any size as decimal digits, from RESP3 big number or integer reply
+///```
+///use redis_client::{BigInt, FromRedisValue, Values};
+///
+///let big = BigInt::from_redis_value(Values::BigNumber(String::from("3492890328409238509324850943850943825024385"))).unwrap();
+///assert_eq!("3492890328409238509324850943850943825024385", big.0);
+///```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BigInt(pub String);
+
+impl FromRedisValue for BigInt {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::BigNumber(digits) => Ok(BigInt(digits)),
+            Values::Integers(i) => Ok(BigInt(i.to_string())),
+            Values::BulkString(s) | Values::SimpleString(s) => {
+                let digits = s.strip_prefix('-').unwrap_or(&s);
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(RedisError::Protocol(format!("invalid big number: {}", s)));
+                }
+
+                Ok(BigInt(s))
+            },
+            other => unexpected("big number", other),
+        }
+    }
+}
+
+impl FromRedisValue for bool {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        Ok(i64::from_redis_value(value)? != 0)
+    }
+}
+
+impl<T: FromRedisValue> FromRedisValue for Option<T> {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::Nil => Ok(None),
+            value => Ok(Some(T::from_redis_value(value)?)),
+        }
+    }
+}
+
+impl<T: FromRedisValue> FromRedisValue for Vec<T> {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value {
+            Values::Arrays(values) => values.into_iter().map(T::from_redis_value).collect(),
+            other => unexpected("array", other),
+        }
+    }
+}
+
+///Fields of hash reply by name, used for mapping hash into struct
+#[derive(Debug, Default)]
+pub struct HashFields(HashMap<String, Values>);
+
+impl HashFields {
+    ///Remove field and convert it, missing field is allowed only for `Option`
+    pub fn take<T: FromRedisValue>(&mut self, field: &str) -> Result<T, RedisError> {
+        match self.0.remove(field) {
+            Some(value) => T::from_redis_value(value),
+            None => T::from_redis_value(Values::Nil).map_err(|_| RedisError::MissingField(field.to_string())),
+        }
+    }
+}
+
+impl IntoIterator for HashFields {
+    type Item = (String, Values);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Values>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromRedisValue for HashFields {
+    fn from_redis_value(value: Values) -> Result<Self, RedisError> {
+        match value.into_map()? {
+            Values::Map(pairs) => Ok(HashFields(pairs.into_iter()
+                .map(|(field, value)| Ok((String::from_redis_value(field)?, value)))
+                .collect::<Result<_, RedisError>>()?)),
+            other => unexpected("map", other),
+        }
+    }
+}
+
+///Implement `FromRedisValue` for struct from hash reply, field names are used as hash fields
+///```
+///use redis_client::{redis_hash_to_struct, FromRedisValue, Values};
+///
+///struct User {
+///    name: String,
+///    email: Option<String>,
+///}
+///
+///redis_hash_to_struct!(User { name, email });
+///
+///let user = User::from_redis_value(Values::Arrays(vec![
+///    Values::BulkString(String::from("name")),
+///    Values::BulkString(String::from("Bob")),
+///])).unwrap();
+///assert_eq!(None, user.email);
+///```
+#[macro_export]
+macro_rules! redis_hash_to_struct {
+    ($name:ident { $($field:ident),* $(,)? }) => {
+        impl $crate::FromRedisValue for $name {
+            fn from_redis_value(value: $crate::Values) -> Result<Self, $crate::RedisError> {
+                let mut fields = <$crate::HashFields as $crate::FromRedisValue>::from_redis_value(value)?;
+
+                Ok($name { $($field: fields.take(stringify!($field))?,)* })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BigInt, FromRedisValue, RedisError, Values};
+
+    #[derive(Debug, PartialEq)]
+    struct User {
+        name: String,
+        age: i64,
+        email: Option<String>,
+    }
+
+    redis_hash_to_struct!(User { name, age, email });
+
+    fn bulk(s: &str) -> Values {
+        Values::BulkString(s.to_string())
+    }
+
+    #[test]
+    fn hash_into_struct() {
+        let hash = Values::Arrays(vec![bulk("name"), bulk("Bob"), bulk("age"), bulk("42")]);
+
+        assert_eq!(
+            User { name: String::from("Bob"), age: 42, email: None },
+            User::from_redis_value(hash).unwrap()
+        );
+    }
+
+    #[test]
+    fn doubles_of_both_protocols() {
+        assert_eq!(2.5, f64::from_redis_value(Values::Double(2.5)).unwrap());
+        assert_eq!(2.5, f64::from_redis_value(bulk("2.5")).unwrap());
+        assert!(f64::from_redis_value(crate::parse_frame(b",inf\r\n").unwrap().0).unwrap().is_infinite());
+    }
+
+    #[test]
+    fn big_number_into_big_int() {
+        let digits = "3492890328409238509324850943850943825024385";
+        let (value, _) = crate::parse_frame(format!("({}\r\n", digits).as_bytes()).unwrap();
+
+        assert_eq!(BigInt(digits.to_string()), BigInt::from_redis_value(value).unwrap());
+        assert_eq!(BigInt(String::from("-42")), BigInt::from_redis_value(Values::Integers(-42)).unwrap());
+        assert!(BigInt::from_redis_value(bulk("12a")).is_err());
+    }
+
+    #[test]
+    fn hash_without_required_field() {
+        let hash = Values::Arrays(vec![bulk("name"), bulk("Bob")]);
+
+        match User::from_redis_value(hash) {
+            Err(RedisError::MissingField(field)) => assert_eq!("age", field),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}